@@ -0,0 +1,232 @@
+/*
+ * This file is part of the ce-rs (https://github.com/dkm/ce-rs)
+ * Copyright (c) 2023 Marc Poulhiès <dkm@kataplop.net>.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! On-disk cache for the (large and slowly changing) catalogs served by a
+//! Compiler-Explorer instance: compilers and languages.
+
+use crate::{check_status, Client, Error};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How `Client` caches catalogs. The default disables the on-disk cache.
+pub struct CacheConfig {
+    /// Root of the cache, `None` disables caching.
+    pub dir: Option<PathBuf>,
+    /// Seconds after which an entry must be revalidated against the server.
+    pub ttl: u64,
+    /// Ignore existing entries and download everything again.
+    pub refresh: bool,
+    /// Never hit the network, only use existing entries.
+    pub offline: bool,
+    /// Called when an entry can't be written. The cache is best effort, so
+    /// such failures are otherwise ignored.
+    pub on_write_error: Option<fn(&Path, &std::io::Error)>,
+    /// Called with the entry name when an expired entry can't be revalidated
+    /// (network or server failure) and is returned anyway.
+    pub on_stale_fallback: Option<fn(&str, &Error)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    fetched_at: u64,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: String,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            dir: None,
+            ttl: 24 * 60 * 60,
+            refresh: false,
+            offline: false,
+            on_write_error: None,
+            on_stale_fallback: None,
+        }
+    }
+}
+
+impl CacheConfig {
+    /// `$XDG_CACHE_HOME/ce-rs`, falling back to `$HOME/.cache/ce-rs`.
+    pub fn default_dir() -> Option<PathBuf> {
+        std::env::var_os("XDG_CACHE_HOME")
+            .filter(|d| !d.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".cache")))
+            .map(|d| d.join("ce-rs"))
+    }
+
+    fn entry_path(&self, base_url: &str, name: &str) -> Option<PathBuf> {
+        // One directory per instance, so that several base URLs can coexist.
+        self.dir.as_ref().map(|d| {
+            d.join(instance_dir(base_url))
+                .join(format!("{}.json", name))
+        })
+    }
+}
+
+/// Directory name for the instance at `base_url`. Characters other than
+/// alphanumerics, `.` and `-` are escaped as `_` and their hex code, so that
+/// different URLs never share a directory.
+fn instance_dir(base_url: &str) -> String {
+    let mut dir = String::new();
+    for b in base_url.bytes() {
+        if b.is_ascii_alphanumeric() || b == b'.' || b == b'-' {
+            dir.push(b as char);
+        } else {
+            dir.push_str(&format!("_{:02X}", b));
+        }
+    }
+    dir
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn read_entry(path: &PathBuf) -> Option<CacheEntry> {
+    let data = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+fn write_entry(config: &CacheConfig, path: &PathBuf, entry: &CacheEntry) {
+    let written = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(path, serde_json::to_string(entry).unwrap()));
+
+    if let (Err(e), Some(on_write_error)) = (written, config.on_write_error) {
+        on_write_error(path, &e);
+    }
+}
+
+/// GETs `url` and returns the body, going through the cache entry `name`.
+///
+/// Fresh entries are returned as is. Stale ones are revalidated with
+/// `If-None-Match`/`If-Modified-Since` when the server gave us an `ETag` or a
+/// `Last-Modified` header, and returned anyway when that fails.
+pub(crate) async fn cached_get(
+    client: &Client,
+    url: reqwest::Url,
+    name: &str,
+) -> Result<String, Error> {
+    let config = &client.cache;
+    let path = config.entry_path(client.base_url(), name);
+    let entry = match (&path, config.refresh) {
+        (Some(p), false) => read_entry(p),
+        _ => None,
+    };
+
+    if config.offline {
+        return entry.map(|e| e.body).ok_or(Error::CacheMiss {
+            name: name.to_string(),
+        });
+    }
+
+    if let Some(e) = &entry {
+        if now().saturating_sub(e.fetched_at) < config.ttl {
+            return Ok(e.body.clone());
+        }
+    }
+
+    let fresh = match (fetch(client, url, entry.as_ref()).await, entry) {
+        (Ok(Some(fetched)), _) => fetched,
+        (Ok(None), Some(cached)) => CacheEntry {
+            fetched_at: now(),
+            ..cached
+        },
+        (Ok(None), None) => unreachable!("only conditional requests get a 304"),
+        (Err(e), Some(stale)) => {
+            if let Some(on_stale_fallback) = config.on_stale_fallback {
+                on_stale_fallback(name, &e);
+            }
+            return Ok(stale.body);
+        }
+        (Err(e), None) => return Err(e),
+    };
+
+    if let Some(p) = &path {
+        write_entry(config, p, &fresh);
+    }
+    Ok(fresh.body)
+}
+
+/// GETs `url`, conditionally when `cached` has validators. `None` when the
+/// server answered that `cached` is still up to date.
+async fn fetch(
+    client: &Client,
+    url: reqwest::Url,
+    cached: Option<&CacheEntry>,
+) -> Result<Option<CacheEntry>, Error> {
+    let mut request = client.client.get(url).header("Accept", "application/json");
+
+    if let Some(e) = cached {
+        if let Some(etag) = &e.etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &e.last_modified {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let resp = request.send().await?;
+
+    if cached.is_some() && resp.status() == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(None);
+    }
+
+    let header = |h: reqwest::header::HeaderName| {
+        resp.headers()
+            .get(h)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string())
+    };
+    let etag = header(reqwest::header::ETAG);
+    let last_modified = header(reqwest::header::LAST_MODIFIED);
+
+    let body = check_status(resp).await?.text().await?;
+    Ok(Some(CacheEntry {
+        fetched_at: now(),
+        etag,
+        last_modified,
+        body,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instance_dirs() {
+        assert_eq!(
+            instance_dir("https://godbolt.org/"),
+            "https_3A_2F_2Fgodbolt.org_2F"
+        );
+        assert_eq!(
+            instance_dir("http://localhost:10240/ce_1/"),
+            "http_3A_2F_2Flocalhost_3A10240_2Fce_5F1_2F"
+        );
+        // Used to be both "http___a_b_".
+        assert_ne!(instance_dir("http://a/b/"), instance_dir("http://a_b/"));
+    }
+}
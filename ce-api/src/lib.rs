@@ -0,0 +1,262 @@
+/*
+ * This file is part of the ce-rs (https://github.com/dkm/ce-rs)
+ * Copyright (c) 2023 Marc Poulhiès <dkm@kataplop.net>.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Client for the [Compiler-Explorer](https://compiler-explorer.org) REST API.
+//!
+//! ``` no_run
+//! # async fn example() -> Result<(), ce_api::Error> {
+//! use ce_api::types::{CompileJob, Filters};
+//!
+//! let client = ce_api::Client::new("https://godbolt.org")?;
+//! let job = CompileJob::build("int main() { return 0; }", "-O2", &Filters::new());
+//! let result = client.compile("g132", job).await?;
+//! println!("{}", result.asm.to_text());
+//! # Ok(())
+//! # }
+//! ```
+
+mod cache;
+pub mod types;
+
+use cache::cached_get;
+pub use cache::CacheConfig;
+use reqwest::Url;
+use thiserror::Error;
+use types::*;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Reqwest error: {source}")]
+    Reqwest {
+        #[from]
+        source: reqwest::Error,
+    },
+
+    #[error("Serde error: {source}")]
+    Serde {
+        #[from]
+        source: serde_json::Error,
+    },
+
+    #[error("HTTP error {status}: {body}")]
+    HttpStatus {
+        status: reqwest::StatusCode,
+        body: String,
+    },
+
+    #[error("Invalid base URL \"{url}\": {reason}")]
+    InvalidUrl { url: String, reason: String },
+
+    #[error("{name} is not in the cache (offline mode)")]
+    CacheMiss { name: String },
+
+    #[error("Internal Error")]
+    Internal,
+}
+
+/// Turns 4xx/5xx responses into `Error::HttpStatus`, keeping the body as
+/// Compiler-Explorer usually explains the failure there.
+pub(crate) async fn check_status(resp: reqwest::Response) -> Result<reqwest::Response, Error> {
+    let status = resp.status();
+    if status.is_client_error() || status.is_server_error() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(Error::HttpStatus { status, body });
+    }
+    Ok(resp)
+}
+
+/// Connection to a Compiler-Explorer instance.
+pub struct Client {
+    base_url: Url,
+    client: reqwest::Client,
+    cache: CacheConfig,
+}
+
+impl Client {
+    pub fn new(base_url: &str) -> Result<Self, Error> {
+        Self::with_user_agent(
+            base_url,
+            concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")),
+        )
+    }
+
+    pub fn with_user_agent(base_url: &str, user_agent: &str) -> Result<Self, Error> {
+        let invalid = |reason: String| Error::InvalidUrl {
+            url: base_url.to_string(),
+            reason,
+        };
+        let parsed = Url::parse(base_url).map_err(|e| invalid(e.to_string()))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(invalid("expected an http or https URL".to_string()));
+        }
+
+        Ok(Client {
+            base_url: parsed,
+            client: reqwest::Client::builder().user_agent(user_agent).build()?,
+            cache: CacheConfig::default(),
+        })
+    }
+
+    /// Sets how the compiler and language catalogs are cached.
+    pub fn cache(mut self, cache: CacheConfig) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        self.base_url.as_str()
+    }
+
+    /// `<base URL>/api/<segments...>`, each segment being escaped.
+    fn api_url(&self, segments: &[&str]) -> Url {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .expect("http(s) URLs always have a path")
+            .pop_if_empty()
+            .push("api")
+            .extend(segments);
+        url
+    }
+
+    pub async fn languages(&self) -> Result<Vec<Language>, Error> {
+        let url = self.api_url(&["languages"]);
+
+        let resp = cached_get(self, url, "languages").await?;
+        let resp = serde_json::from_str::<Vec<Language>>(&resp)?;
+        Ok(resp)
+    }
+
+    /// Lists all the compilers of the instance. Without `all_fields`, only
+    /// the main fields of `CompilerInfo` are filled.
+    pub async fn compilers(&self, all_fields: bool) -> Result<Vec<CompilerInfo>, Error> {
+        let mut url = self.api_url(&["compilers"]);
+        if all_fields {
+            url.query_pairs_mut().append_pair("fields", "all");
+        }
+
+        let cache_name = if all_fields {
+            "compilers-all"
+        } else {
+            "compilers"
+        };
+
+        let resp = cached_get(self, url, cache_name).await?;
+        let resp = serde_json::from_str::<Vec<CompilerInfo>>(&resp)?;
+        Ok(resp)
+    }
+
+    /// Lists the compilers for the language `language_id`.
+    pub async fn compilers_for_language(
+        &self,
+        language_id: &str,
+    ) -> Result<Vec<CompilerInfo>, Error> {
+        let resp = self
+            .client
+            .get(self.api_url(&["compilers", language_id]))
+            .header("Accept", "application/json")
+            .send()
+            .await?;
+
+        let resp = check_status(resp)
+            .await?
+            .json::<Vec<CompilerInfo>>()
+            .await?;
+        Ok(resp)
+    }
+
+    /// Lists the libraries available for the language `language_id`.
+    pub async fn libraries(&self, language_id: &str) -> Result<Vec<LibraryInfo>, Error> {
+        let url = self.api_url(&["libraries", language_id]);
+
+        let cache_name = format!("libraries-{}", language_id);
+        let resp = cached_get(self, url, &cache_name).await?;
+        let resp = serde_json::from_str::<Vec<LibraryInfo>>(&resp)?;
+        Ok(resp)
+    }
+
+    /// Lists the tools available for the language `language_id`.
+    pub async fn tools(&self, language_id: &str) -> Result<Vec<ToolInfo>, Error> {
+        let url = self.api_url(&["tools", language_id]);
+
+        let cache_name = format!("tools-{}", language_id);
+        let resp = cached_get(self, url, &cache_name).await?;
+        let resp = serde_json::from_str::<Vec<ToolInfo>>(&resp)?;
+        Ok(resp)
+    }
+
+    pub async fn shortlinkinfo(&self, shortlink: &str) -> Result<ShortLinkInfo, Error> {
+        let resp = self
+            .client
+            .get(self.api_url(&["shortlinkinfo", shortlink]))
+            .header("Accept", "application/json")
+            .send()
+            .await?;
+
+        let resp = check_status(resp).await?.json::<ShortLinkInfo>().await?;
+        Ok(resp)
+    }
+
+    /// Stores `state` on the instance and returns the URL of the shortlink.
+    pub async fn shorten(&self, state: &ShortLinkInfo) -> Result<String, Error> {
+        let resp = self
+            .client
+            .post(self.api_url(&["shortener"]))
+            .header("Accept", "application/json")
+            .json(state)
+            .send()
+            .await?;
+
+        let resp = check_status(resp).await?.json::<ShortenerResult>().await?;
+        Ok(resp.url)
+    }
+
+    pub async fn compile(
+        &self,
+        compiler_id: &str,
+        job: CompileJob,
+    ) -> Result<CompileJobResult, Error> {
+        let resp = self
+            .client
+            .post(self.api_url(&["compiler", compiler_id, "compile"]))
+            .header("Accept", "application/json")
+            .json(&job)
+            .send()
+            .await?;
+
+        let resp = check_status(resp).await?.json::<CompileJobResult>().await?;
+        Ok(resp)
+    }
+
+    /// Builds a CMake project with the compiler `compiler_id`: `job.source`
+    /// is the `CMakeLists.txt` and `job.files` the rest of the project.
+    pub async fn cmake(
+        &self,
+        compiler_id: &str,
+        job: CompileJob,
+    ) -> Result<CompileJobResult, Error> {
+        let resp = self
+            .client
+            .post(self.api_url(&["compiler", compiler_id, "cmake"]))
+            .header("Accept", "application/json")
+            .json(&job)
+            .send()
+            .await?;
+
+        let resp = check_status(resp).await?.json::<CompileJobResult>().await?;
+        Ok(resp)
+    }
+}
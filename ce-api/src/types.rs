@@ -17,7 +17,7 @@ use std::collections::HashMap;
 
 #[allow(dead_code)]
 #[allow(non_snake_case)]
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct Language {
     pub id: String,
     pub name: String,
@@ -41,7 +41,7 @@ pub struct Language {
 
 #[allow(dead_code)]
 #[allow(non_snake_case)]
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct CompilerInfo {
     pub id: String,
     pub name: String,
@@ -144,9 +144,9 @@ pub struct CompilerInfo {
 
 #[allow(dead_code)]
 #[allow(non_snake_case)]
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct Session {
-    pub id: u8,
+    pub id: usize,
     pub language: String,
     pub source: String,
     pub conformanceview: bool,
@@ -154,9 +154,10 @@ pub struct Session {
     pub executors: Vec<ExecutorConfig>,
 }
 
+/// State of the web UI, as stored behind a shortlink.
 #[allow(dead_code)]
 #[allow(non_snake_case)]
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct ShortLinkInfo {
     pub sessions: Vec<Session>,
     pub trees: Vec<Tree>,
@@ -164,41 +165,98 @@ pub struct ShortLinkInfo {
 
 #[allow(dead_code)]
 #[allow(non_snake_case)]
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct Tree {}
 
 #[allow(dead_code)]
 #[allow(non_snake_case)]
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct ExecutorConfig {}
 
 #[allow(dead_code)]
 #[allow(non_snake_case)]
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct CompilerConfig {
-    pub _internalid: u8,
+    pub _internalid: usize,
     pub id: String,
     pub options: String,
     pub filters: Filters,
+    #[serde(serialize_with = "serialize_client_state_libs")]
     pub libs: Vec<Library>,
     pub specialoutputs: Vec<Output>,
     pub tools: Vec<Tool>,
 }
 
+// Client states spell libraries {name, ver} where compile requests use
+// {id, version}.
+fn serialize_client_state_libs<S: serde::Serializer>(
+    libs: &[Library],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    #[derive(Serialize)]
+    struct ClientStateLibrary<'a> {
+        name: &'a str,
+        ver: &'a str,
+    }
+
+    serializer.collect_seq(libs.iter().map(|l| ClientStateLibrary {
+        name: &l.id,
+        ver: &l.version,
+    }))
+}
+
 #[allow(dead_code)]
 #[allow(non_snake_case)]
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct Output {}
 
+#[allow(dead_code)]
+#[allow(non_snake_case)]
+#[derive(Deserialize, Debug)]
+pub struct ShortenerResult {
+    pub url: String,
+}
+
 #[allow(dead_code)]
 #[allow(non_snake_case)]
 #[derive(Deserialize, Serialize, Debug, Clone)]
-pub struct Library {}
+pub struct Library {
+    // Client states (shortlinks) use name/ver, compile requests id/version.
+    #[serde(alias = "name")]
+    pub id: String,
+    #[serde(alias = "ver")]
+    pub version: String,
+}
+
+/// A library available for a language, as listed by `/api/libraries`.
+#[allow(dead_code)]
+#[allow(non_snake_case)]
+#[derive(Deserialize, Debug, Clone)]
+pub struct LibraryInfo {
+    pub id: String,
+    pub name: String,
+    pub url: Option<String>,
+    pub description: Option<String>,
+    pub versions: Vec<LibraryVersion>,
+}
+
+#[allow(dead_code)]
+#[allow(non_snake_case)]
+#[derive(Deserialize, Debug, Clone)]
+pub struct LibraryVersion {
+    pub id: String,
+    pub version: Option<String>,
+    pub alias: Option<Vec<String>>,
+    pub hidden: Option<bool>,
+}
 
 #[allow(dead_code)]
 #[allow(non_snake_case)]
 #[derive(Deserialize, Serialize, Debug, Clone)]
-pub struct Tool {}
+pub struct Tool {
+    pub id: String,
+    pub args: String,
+}
 
 #[allow(dead_code)]
 #[allow(non_snake_case)]
@@ -224,6 +282,16 @@ pub struct CompileJob {
     pub options: CompileOptions,
     pub lang: Option<String>,
     pub allowStoreCodeDebug: bool,
+    pub files: Vec<FiledataPair>,
+}
+
+/// A file given along the main source, with its path relative to it.
+#[allow(dead_code)]
+#[allow(non_snake_case)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct FiledataPair {
+    pub filename: String,
+    pub contents: String,
 }
 #[allow(dead_code)]
 #[allow(non_snake_case)]
@@ -234,6 +302,16 @@ pub struct CompileOptions {
     pub filters: Filters,
     pub tools: Vec<Tool>,
     pub libraries: Vec<Library>,
+    pub executeParameters: ExecuteParameters,
+}
+
+/// Arguments and standard input given to the program when executing.
+#[allow(dead_code)]
+#[allow(non_snake_case)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct ExecuteParameters {
+    pub args: Vec<String>,
+    pub stdin: String,
 }
 
 #[allow(dead_code)]
@@ -246,22 +324,52 @@ pub struct OtherCompilerOptions {
 
 #[allow(dead_code)]
 #[allow(non_snake_case)]
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct Download {}
 
 #[allow(dead_code)]
 #[allow(non_snake_case)]
-#[derive(Deserialize, Debug)]
-pub struct ToolResult {}
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ToolResult {
+    pub id: String,
+    pub name: Option<String>,
+    pub code: i32,
+    pub stdout: SomeOutput,
+    pub stderr: SomeOutput,
+}
 
+/// A tool available for a language, as listed by `/api/tools`.
 #[allow(dead_code)]
 #[allow(non_snake_case)]
-#[derive(Deserialize, Debug)]
-pub struct Label {}
+#[derive(Deserialize, Debug, Clone)]
+pub struct ToolInfo {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub toolType: Option<String>,
+    pub languageId: Option<String>,
+    pub allowStdin: Option<bool>,
+}
 
 #[allow(dead_code)]
 #[allow(non_snake_case)]
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Label {
+    pub name: String,
+    pub range: LabelRange,
+}
+
+#[allow(dead_code)]
+#[allow(non_snake_case)]
+#[derive(Deserialize, Serialize, Debug)]
+pub struct LabelRange {
+    pub startCol: i32,
+    pub endCol: i32,
+}
+
+#[allow(dead_code)]
+#[allow(non_snake_case)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct Tag {
     pub line: i32,
     pub column: i32,
@@ -272,12 +380,12 @@ pub struct Tag {
 
 #[allow(dead_code)]
 #[allow(non_snake_case)]
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct SomeOutput(Vec<OutputItem>);
 
 #[allow(dead_code)]
 #[allow(non_snake_case)]
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct OutputItem {
     pub text: String,
     pub tag: Option<Tag>,
@@ -285,7 +393,7 @@ pub struct OutputItem {
 
 #[allow(dead_code)]
 #[allow(non_snake_case)]
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct SourceLocation {
     pub file: Option<String>,
     pub line: i32,
@@ -293,12 +401,12 @@ pub struct SourceLocation {
 
 #[allow(dead_code)]
 #[allow(non_snake_case)]
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct AsmOutput(Vec<AsmOutputItem>);
 
 #[allow(dead_code)]
 #[allow(non_snake_case)]
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct AsmOutputItem {
     pub text: String,
     pub source: Option<SourceLocation>,
@@ -307,14 +415,14 @@ pub struct AsmOutputItem {
 
 #[allow(dead_code)]
 #[allow(non_snake_case)]
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct PopularArgument {
     pub description: String,
     pub timesused: i32,
 }
 
 #[allow(dead_code)]
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 #[allow(non_snake_case)]
 pub struct CompileJobResult {
     pub inputFilename: String,
@@ -338,7 +446,7 @@ pub struct CompileJobResult {
 }
 
 #[allow(dead_code)]
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 #[allow(non_snake_case)]
 pub struct ExecutionResult {
     pub code: i32,
@@ -354,7 +462,7 @@ pub struct ExecutionResult {
 }
 
 #[allow(dead_code)]
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 #[allow(non_snake_case)]
 pub struct ExecBuildResult {
     pub inputFilename: String,
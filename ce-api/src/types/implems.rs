@@ -0,0 +1,656 @@
+use crate::types::*;
+
+/// Sections of `CompilerInfo::to_details()`, with the fields they show.
+const COMPILER_DETAILS: [(&str, &[&str]); 5] = [
+    (
+        "General",
+        &[
+            "id",
+            "name",
+            "lang",
+            "compilerType",
+            "semver",
+            "version",
+            "fullVersion",
+            "baseName",
+            "alias",
+            "instructionSet",
+            "group",
+            "groupName",
+            "compilerCategories",
+            "isSemVer",
+            "isNightly",
+            "hidden",
+            "interpreted",
+            "notification",
+        ],
+    ),
+    (
+        "Invocation",
+        &[
+            "exe",
+            "options",
+            "unwiseOptions",
+            "versionRe",
+            "explicitVersion",
+            "executionWrapper",
+            "executionWrapperArgs",
+            "includeFlag",
+            "includePath",
+            "linkFlag",
+            "rpathFlag",
+            "libpathFlag",
+            "libPath",
+            "ldPath",
+            "extraPath",
+            "postProcess",
+            "needsMulti",
+        ],
+    ),
+    (
+        "Tools",
+        &[
+            "demangler",
+            "demanglerType",
+            "demanglerArgs",
+            "objdumper",
+            "objdumperType",
+            "objdumperArgs",
+            "intelAsm",
+            "nvdisasm",
+            "adarts",
+            "debugPatched",
+        ],
+    ),
+    (
+        "Views",
+        &[
+            "optArg",
+            "stackUsageArg",
+            "irArg",
+            "minIrArgs",
+            "llvmOptArg",
+            "llvmOptModuleScopeArg",
+            "llvmOptNoDiscardValueNamesArg",
+            "removeEmptyGccDump",
+        ],
+    ),
+    ("Filters", &["disabledFilters"]),
+];
+
+/// False for nulls, empty strings and empty lists.
+pub fn is_populated(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Null => false,
+        serde_json::Value::String(s) => !s.is_empty(),
+        serde_json::Value::Array(a) => !a.is_empty(),
+        _ => true,
+    }
+}
+
+fn detail_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Bool(b) => (if *b { "yes" } else { "no" }).to_string(),
+        serde_json::Value::Array(a) => a
+            .iter()
+            .map(detail_value)
+            .collect::<Vec<String>>()
+            .join(", "),
+        v => v.to_string(),
+    }
+}
+
+impl CompilerInfo {
+    /// Every populated field, grouped in sections. The `supports*` fields are
+    /// summarized as the list of supported features.
+    pub fn to_details(&self) -> String {
+        let serde_json::Value::Object(mut fields) = serde_json::to_value(self).unwrap() else {
+            unreachable!("a struct serializes to an object");
+        };
+        fields.retain(|k, v| is_populated(v) && !k.starts_with("supports"));
+        let width = fields.keys().map(|k| k.len() + 1).max().unwrap_or(0);
+
+        let mut out = Vec::new();
+        for (title, keys) in COMPILER_DETAILS {
+            let lines = keys
+                .iter()
+                .filter_map(|k| fields.remove(*k).map(|v| (k.to_string(), v)))
+                .map(|(k, v)| format!("  {:<width$} {}", format!("{}:", k), detail_value(&v)))
+                .collect::<Vec<String>>();
+            if !lines.is_empty() {
+                out.push(format!("{}:", title));
+                out.extend(lines);
+            }
+        }
+        if !fields.is_empty() {
+            out.push("Other:".to_string());
+            for (k, v) in &fields {
+                out.push(format!(
+                    "  {:<width$} {}",
+                    format!("{}:", k),
+                    detail_value(v)
+                ));
+            }
+        }
+
+        let supported = Self::FEATURES
+            .iter()
+            .filter(|f| self.supports(f) == Some(true))
+            .copied()
+            .collect::<Vec<&str>>();
+        out.push(format!(
+            "Supports: {}",
+            if supported.is_empty() {
+                "-".to_string()
+            } else {
+                supported.join(", ")
+            }
+        ));
+        out.join("\n")
+    }
+
+    pub fn to_text(&self) -> String {
+        format!(
+            "\"{}\", id: {}, language: {}, type: {}, version: {}, ISA: {}",
+            self.name, self.id, self.lang, self.compilerType, self.semver, self.instructionSet
+        )
+    }
+
+    /// Features that can be checked with `supports()`, named after the
+    /// `supports*` fields.
+    pub const FEATURES: [&'static str; 27] = [
+        "asmDocs",
+        "astView",
+        "binary",
+        "binaryObject",
+        "cfg",
+        "demangle",
+        "deviceAsmView",
+        "execute",
+        "filtersInBinary",
+        "gccDump",
+        "gnatDebugViews",
+        "haskellCmmView",
+        "haskellCoreView",
+        "haskellStgView",
+        "intel",
+        "irView",
+        "libraryCodeFilter",
+        "llvmOptPipelineView",
+        "march",
+        "optOutput",
+        "ppView",
+        "rustHirView",
+        "rustMacroExpView",
+        "rustMirView",
+        "stackUsageOutput",
+        "target",
+        "targetIs",
+    ];
+
+    /// Whether the compiler supports `feature` (see `FEATURES`), `None` if
+    /// there is no such feature.
+    pub fn supports(&self, feature: &str) -> Option<bool> {
+        let supported = match feature {
+            "asmDocs" => self.supportsAsmDocs,
+            "astView" => self.supportsAstView,
+            "binary" => self.supportsBinary,
+            "binaryObject" => self.supportsBinaryObject,
+            "cfg" => self.supportsCfg,
+            "demangle" => self.supportsDemangle,
+            "deviceAsmView" => self.supportsDeviceAsmView,
+            "execute" => self.supportsExecute,
+            "filtersInBinary" => self.supportsFiltersInBinary,
+            "gccDump" => self.supportsGccDump,
+            "gnatDebugViews" => self.supportsGnatDebugViews,
+            "haskellCmmView" => self.supportsHaskellCmmView,
+            "haskellCoreView" => self.supportsHaskellCoreView,
+            "haskellStgView" => self.supportsHaskellStgView,
+            "intel" => self.supportsIntel,
+            "irView" => self.supportsIrView,
+            "libraryCodeFilter" => self.supportsLibraryCodeFilter,
+            "llvmOptPipelineView" => self.supportsLLVMOptPipelineView,
+            "march" => self.supportsMarch,
+            "optOutput" => self.supportsOptOutput,
+            "ppView" => self.supportsPpView,
+            "rustHirView" => self.supportsRustHirView,
+            "rustMacroExpView" => self.supportsRustMacroExpView,
+            "rustMirView" => self.supportsRustMirView,
+            "stackUsageOutput" => self.supportsStackUsageOutput,
+            "target" => self.supportsTarget,
+            "targetIs" => self.supportsTargetIs,
+            _ => return None,
+        };
+        Some(supported.unwrap_or(false))
+    }
+
+    /// Whether the compiler can honor the filter `name` (see `Filters::NAMES`),
+    /// according to its `supports*` fields and `disabledFilters`.
+    pub fn supports_filter(&self, name: &str) -> bool {
+        if self
+            .disabledFilters
+            .as_ref()
+            .is_some_and(|disabled| disabled.iter().any(|f| f == name))
+        {
+            return false;
+        }
+        let feature = match name {
+            "libraryCode" => "libraryCodeFilter",
+            "binary" | "binaryObject" | "demangle" | "execute" | "intel" => name,
+            _ => return true,
+        };
+        self.supports(feature).unwrap_or(true)
+    }
+}
+impl Language {
+    /// Every field, `-` when unset. The example program comes last, indented.
+    pub fn to_details(&self) -> String {
+        let serde_json::Value::Object(fields) = serde_json::to_value(self).unwrap() else {
+            unreachable!("a struct serializes to an object");
+        };
+        let width = fields.keys().map(|k| k.len() + 1).max().unwrap_or(0);
+
+        let mut out = fields
+            .iter()
+            .filter(|(k, _)| *k != "example")
+            .map(|(k, v)| {
+                let value = if is_populated(v) {
+                    detail_value(v)
+                } else {
+                    "-".to_string()
+                };
+                format!("{:<width$} {}", format!("{}:", k), value)
+            })
+            .collect::<Vec<String>>();
+        match &self.example {
+            Some(example) if !example.is_empty() => {
+                out.push("example:".to_string());
+                out.extend(example.lines().map(|l| format!("  {}", l)));
+            }
+            _ => out.push(format!("{:<width$} -", "example:")),
+        }
+        out.join("\n")
+    }
+}
+
+impl Default for Filters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ToolInfo {
+    pub fn to_text(&self) -> String {
+        format!(
+            "\"{}\", id: {}, type: {}",
+            self.name,
+            self.id,
+            self.toolType.as_deref().unwrap_or("-")
+        )
+    }
+}
+
+impl ToolResult {
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.id)
+    }
+}
+
+impl LibraryInfo {
+    pub fn to_text(&self) -> String {
+        let versions = self
+            .versions
+            .iter()
+            .map(|v| v.to_text())
+            .collect::<Vec<String>>()
+            .join(", ");
+        format!("\"{}\", id: {}, versions: {}", self.name, self.id, versions)
+    }
+
+    /// Finds a version by id, version string or alias.
+    pub fn find_version(&self, version: &str) -> Option<&LibraryVersion> {
+        self.versions.iter().find(|v| {
+            v.id == version
+                || v.version.as_deref() == Some(version)
+                || v.alias
+                    .as_ref()
+                    .is_some_and(|a| a.iter().any(|a| a == version))
+        })
+    }
+}
+
+impl LibraryVersion {
+    pub fn to_text(&self) -> String {
+        match &self.version {
+            Some(v) if *v != self.id => format!("{} ({})", self.id, v),
+            _ => self.id.clone(),
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+impl Filters {
+    /// Filter names, as used by the Compiler-Explorer API.
+    pub const NAMES: [&'static str; 11] = [
+        "binary",
+        "binaryObject",
+        "commentOnly",
+        "demangle",
+        "directives",
+        "execute",
+        "intel",
+        "labels",
+        "libraryCode",
+        "trim",
+        "debugCalls",
+    ];
+
+    fn field(&mut self, name: &str) -> Option<&mut bool> {
+        match name {
+            "binary" => Some(&mut self.binary),
+            "binaryObject" => Some(&mut self.binaryObject),
+            "commentOnly" => Some(&mut self.commentOnly),
+            "demangle" => Some(&mut self.demangle),
+            "directives" => Some(&mut self.directives),
+            "execute" => Some(&mut self.execute),
+            "intel" => Some(&mut self.intel),
+            "labels" => Some(&mut self.labels),
+            "libraryCode" => Some(&mut self.libraryCode),
+            "trim" => Some(&mut self.trim),
+            "debugCalls" => Some(&mut self.debugCalls),
+            _ => None,
+        }
+    }
+
+    /// Enables or disables the filter `name`, returns false if there is no
+    /// such filter.
+    pub fn set(&mut self, name: &str, v: bool) -> bool {
+        self.field(name).map(|f| *f = v).is_some()
+    }
+
+    /// Names of the enabled filters.
+    pub fn enabled(&self) -> Vec<&'static str> {
+        let values = [
+            self.binary,
+            self.binaryObject,
+            self.commentOnly,
+            self.demangle,
+            self.directives,
+            self.execute,
+            self.intel,
+            self.labels,
+            self.libraryCode,
+            self.trim,
+            self.debugCalls,
+        ];
+        Self::NAMES
+            .into_iter()
+            .zip(values)
+            .filter_map(|(name, v)| v.then_some(name))
+            .collect()
+    }
+
+    pub fn all_disabled() -> Self {
+        Filters {
+            binary: false,
+            binaryObject: false,
+            commentOnly: false,
+            demangle: false,
+            directives: false,
+            execute: false,
+            intel: false,
+            labels: false,
+            libraryCode: false,
+            trim: false,
+            debugCalls: false,
+        }
+    }
+
+    pub fn new() -> Self {
+        Filters {
+            binary: false,
+            binaryObject: false,
+            execute: false,
+
+            commentOnly: true,
+            demangle: true,
+            directives: true,
+            intel: true,
+            labels: true,
+            libraryCode: true,
+            trim: false,
+            debugCalls: true,
+        }
+    }
+
+    pub fn binary(mut self, v: bool) -> Self {
+        self.binary = v;
+        self
+    }
+
+    pub fn binary_object(mut self, v: bool) -> Self {
+        self.binaryObject = v;
+        self
+    }
+
+    pub fn comment_only(mut self, v: bool) -> Self {
+        self.commentOnly = v;
+        self
+    }
+
+    pub fn demangle(mut self, v: bool) -> Self {
+        self.demangle = v;
+        self
+    }
+
+    pub fn directives(mut self, v: bool) -> Self {
+        self.directives = v;
+        self
+    }
+
+    pub fn execute(mut self, v: bool) -> Self {
+        self.execute = v;
+        self
+    }
+
+    pub fn intel(mut self, v: bool) -> Self {
+        self.intel = v;
+        self
+    }
+
+    pub fn labels(mut self, v: bool) -> Self {
+        self.labels = v;
+        self
+    }
+
+    pub fn libraryCode(mut self, v: bool) -> Self {
+        self.libraryCode = v;
+        self
+    }
+
+    pub fn trim(mut self, v: bool) -> Self {
+        self.trim = v;
+        self
+    }
+
+    pub fn debugCalls(mut self, v: bool) -> Self {
+        self.debugCalls = v;
+        self
+    }
+}
+
+impl CompileJobResult {
+    /// True when the compilation, and the execution if any, exited with 0.
+    pub fn succeeded(&self) -> bool {
+        self.code == 0 && self.execResult.as_ref().is_none_or(|e| e.code == 0)
+    }
+}
+
+impl CompileJob {
+    pub fn build(source: &str, compiler_option: &str, filters: &Filters) -> Self {
+        CompileJob {
+            source: source.to_string(),
+            options: CompileOptions {
+                userArguments: compiler_option.to_string(),
+                compilerOptions: OtherCompilerOptions {
+                    skipAsm: false,
+                    executorRequest: false,
+                },
+                filters: (*filters).clone(),
+                tools: Vec::new(),
+                libraries: Vec::new(),
+                executeParameters: ExecuteParameters::default(),
+            },
+            lang: None,
+            allowStoreCodeDebug: true,
+            files: Vec::new(),
+        }
+    }
+
+    pub fn lang(mut self, lang: &str) -> Self {
+        self.lang = Some(lang.to_string());
+        self
+    }
+
+    pub fn files(mut self, files: Vec<FiledataPair>) -> Self {
+        self.files = files;
+        self
+    }
+
+    pub fn libraries(mut self, libraries: Vec<Library>) -> Self {
+        self.options.libraries = libraries;
+        self
+    }
+
+    pub fn tools(mut self, tools: Vec<Tool>) -> Self {
+        self.options.tools = tools;
+        self
+    }
+
+    pub fn execute_parameters(mut self, args: Vec<String>, stdin: &str) -> Self {
+        self.options.executeParameters = ExecuteParameters {
+            args,
+            stdin: stdin.to_string(),
+        };
+        self
+    }
+
+    pub fn build_simple(source: &str, compiler_option: &str) -> Self {
+        CompileJob {
+            source: source.to_string(),
+            options: CompileOptions {
+                userArguments: compiler_option.to_string(),
+                compilerOptions: OtherCompilerOptions {
+                    skipAsm: false,
+                    executorRequest: false,
+                },
+                filters: Filters {
+                    binary: false,
+                    binaryObject: false,
+                    commentOnly: false,
+                    demangle: false,
+                    directives: false,
+                    execute: false,
+                    intel: false,
+                    labels: false,
+                    libraryCode: false,
+                    trim: false,
+                    debugCalls: false,
+                },
+                tools: Vec::new(),
+                libraries: Vec::new(),
+                executeParameters: ExecuteParameters::default(),
+            },
+            lang: None,
+            allowStoreCodeDebug: true,
+            files: Vec::new(),
+        }
+    }
+}
+
+impl SomeOutput {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Same as `to_text()`, with the diagnostics rewritten as
+    /// `file:line:column: text` from their `Tag`. `main_file` names the main
+    /// source, which has no `Tag.file`.
+    pub fn to_located_text(&self, main_file: &str) -> String {
+        self.0
+            .iter()
+            .map(|x| match &x.tag {
+                Some(tag) if tag.line > 0 => format!(
+                    "{}:{}:{}: {}",
+                    tag.file.as_deref().unwrap_or(main_file),
+                    tag.line,
+                    tag.column,
+                    tag.text
+                ),
+                _ => x.text.clone(),
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    pub fn to_text(&self) -> String {
+        self.0
+            .iter()
+            .map(|x| x.text.clone())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+impl AsmOutput {
+    pub fn items(&self) -> &[AsmOutputItem] {
+        &self.0
+    }
+
+    pub fn to_text(&self) -> String {
+        self.0
+            .iter()
+            .map(|x| x.text.clone())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn language_details_in_field_order() {
+        let language: Language = serde_json::from_value(serde_json::json!({
+            "id": "rust",
+            "name": "Rust",
+            "monaco": "rust",
+            "extensions": [".rs"],
+            "defaultCompiler": "r1700",
+            "example": "fn main() {\n}\n",
+        }))
+        .unwrap();
+        assert_eq!(
+            language.to_details(),
+            "\
+id:                rust
+name:              Rust
+monaco:            rust
+extensions:        .rs
+alias:             -
+formatter:         -
+supportsExecute:   -
+logoUrl:           -
+logoUrlDark:       -
+previewFilter:     -
+monacoDisassembly: -
+tooltip:           -
+defaultCompiler:   r1700
+example:
+  fn main() {
+  }"
+        );
+    }
+}
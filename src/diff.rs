@@ -0,0 +1,177 @@
+/*
+ * This file is part of the ce-rs (https://github.com/dkm/ce-rs)
+ * Copyright (c) 2023 Marc Poulhiès <dkm@kataplop.net>.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use colored::*;
+use regex::{Captures, Regex};
+use similar::{DiffTag, TextDiff};
+use std::collections::HashMap;
+
+/// Colored unified diff going from `old` to `new`.
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    let old = format!("{}\n", old);
+    let new = format!("{}\n", new);
+    TextDiff::from_lines(&old, &new)
+        .unified_diff()
+        .header(old_name, new_name)
+        .to_string()
+        .lines()
+        .map(|line| match line.chars().next() {
+            Some('-') => line.red().to_string(),
+            Some('+') => line.green().to_string(),
+            Some('@') => line.cyan().to_string(),
+            _ => line.to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Colored two-column diff of `old` and `new`, with a marker between the
+/// columns: `<` for removed lines, `>` for added ones and `|` for changed ones.
+pub fn side_by_side_diff(old: &[String], new: &[String], old_name: &str, new_name: &str) -> String {
+    let old = old.iter().map(|l| expand_tabs(l)).collect::<Vec<String>>();
+    let new = new.iter().map(|l| expand_tabs(l)).collect::<Vec<String>>();
+    let width = old
+        .iter()
+        .map(|l| l.chars().count())
+        .chain([old_name.chars().count()])
+        .max()
+        .unwrap_or(0);
+
+    let pad = |s: &str| format!("{:<width$}", s, width = width);
+    let mut out = vec![format!("{}   {}", pad(old_name).bold(), new_name.bold())];
+
+    let old_refs = old.iter().map(String::as_str).collect::<Vec<&str>>();
+    let new_refs = new.iter().map(String::as_str).collect::<Vec<&str>>();
+    let diff = TextDiff::from_slices(&old_refs, &new_refs);
+
+    for op in diff.ops() {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        let old_lines = &old[old_range];
+        let new_lines = &new[new_range];
+        let rows = old_lines.len().max(new_lines.len());
+
+        for i in 0..rows {
+            let left = old_lines.get(i).map(String::as_str);
+            let right = new_lines.get(i).map(String::as_str);
+            let line = match (tag, left, right) {
+                (DiffTag::Equal, Some(l), Some(r)) => format!("{}   {}", pad(l), r),
+                (_, Some(l), Some(r)) => format!("{} {} {}", pad(l).red(), "|".yellow(), r.green()),
+                (_, Some(l), None) => format!("{} {}", pad(l).red(), "<".red()),
+                (_, None, Some(r)) => format!("{} {} {}", pad(""), ">".green(), r.green()),
+                (_, None, None) => continue,
+            };
+            out.push(line);
+        }
+    }
+    out.join("\n")
+}
+
+fn expand_tabs(line: &str) -> String {
+    let mut out = String::new();
+    for c in line.chars() {
+        if c == '\t' {
+            let spaces = 8 - out.chars().count() % 8;
+            out.push_str(&" ".repeat(spaces));
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Renames local labels (`.L3`, `.LBB0_2`, `.Ltmp1`, ...) in order of
+/// appearance and masks addresses, so that two listings only differ by the
+/// code itself.
+///
+/// Addresses only show up in binary mode, as the offset starting each line
+/// and as the target of branches followed by their symbol
+/// (`call 401030 <puts@plt>`). Other numbers are left alone.
+pub fn normalize_asm(lines: &[String]) -> Vec<String> {
+    let label_re = Regex::new(r"\.L[\w$.]+").unwrap();
+    let offset_re = Regex::new(r"^(\s*)([0-9a-f]+):").unwrap();
+    let target_re = Regex::new(r"\b(?:0x)?[0-9a-f]+(\s+<[^>\s]+>)").unwrap();
+    let mut labels: HashMap<String, String> = HashMap::new();
+
+    lines
+        .iter()
+        .map(|line| {
+            let line = label_re.replace_all(line, |caps: &Captures| {
+                let next = format!(".L{}", labels.len());
+                labels.entry(caps[0].to_string()).or_insert(next).clone()
+            });
+            // A label made of hex letters only ("add:") isn't an offset.
+            let line = offset_re.replace(&line, |caps: &Captures| {
+                if caps[2].bytes().any(|b| b.is_ascii_digit()) {
+                    format!("{}<addr>:", &caps[1])
+                } else {
+                    caps[0].to_string()
+                }
+            });
+            target_re.replace_all(&line, "<addr>$1").into_owned()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(lines: &[&str]) -> Vec<String> {
+        normalize_asm(&lines.iter().map(|l| l.to_string()).collect::<Vec<String>>())
+    }
+
+    #[test]
+    fn labels_renamed_in_order() {
+        assert_eq!(
+            normalize(&[
+                ".LBB0_2:",
+                "        jmp     .LBB0_5",
+                ".LBB0_5:",
+                "        jne     .LBB0_2"
+            ]),
+            [".L0:", "        jmp     .L1", ".L1:", "        jne     .L0"]
+        );
+    }
+
+    #[test]
+    fn binary_addresses_masked() {
+        assert_eq!(
+            normalize(&[
+                "  401126:\tpush   rbp",
+                "  40112a:\tcall   401030 <puts@plt>",
+                "  40113f:\tjmp    0x401150 <main+0x2a>",
+            ]),
+            [
+                "  <addr>:\tpush   rbp",
+                "  <addr>:\tcall   <addr> <puts@plt>",
+                "  <addr>:\tjmp    <addr> <main+0x2a>",
+            ]
+        );
+    }
+
+    #[test]
+    fn immediates_unchanged() {
+        let lines = [
+            "        mov     eax, 1234567",
+            "        movabs  rax, 4294967296",
+            "        mov     DWORD PTR [rbp-4], 100000",
+            "        mov     eax, 0xdeadbeef",
+            "add:",
+        ];
+        assert_eq!(normalize(&lines), lines);
+    }
+}
@@ -0,0 +1,258 @@
+/*
+ * This file is part of the ce-rs (https://github.com/dkm/ce-rs)
+ * Copyright (c) 2023 Marc Poulhiès <dkm@kataplop.net>.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::diff::unified_diff;
+use crate::{new_regex, Error};
+use ce_api::types::ExecutionResult;
+use clap::{Arg, ArgMatches};
+use regex::Regex;
+
+/// Arguments asserting on the result of the executed program.
+pub fn expect_args() -> [Arg; 4] {
+    [
+        Arg::new("expect-stdout")
+            .long("expect-stdout")
+            .value_name("file")
+            .help("Expect the program stdout to be the content of file (implies --execute)"),
+        Arg::new("expect-stdout-regex")
+            .long("expect-stdout-regex")
+            .value_name("regex")
+            .help("Expect the program stdout to match regex (implies --execute)"),
+        Arg::new("expect-stderr-regex")
+            .long("expect-stderr-regex")
+            .value_name("regex")
+            .help("Expect the program stderr to match regex (implies --execute)"),
+        Arg::new("expect-exit-code")
+            .long("expect-exit-code")
+            .value_name("N")
+            .value_parser(clap::value_parser!(i32))
+            .allow_negative_numbers(true)
+            .help("Expect the program to exit with N instead of 0 (implies --execute)"),
+    ]
+}
+
+/// Assertions given with `--expect-*`, checked against each execution.
+pub struct ExecExpectations {
+    /// Expected stdout, with the file it was read from.
+    stdout: Option<(String, String)>,
+    stdout_re: Option<Regex>,
+    stderr_re: Option<Regex>,
+    exit_code: Option<i32>,
+}
+
+impl ExecExpectations {
+    pub fn from_matches(matches: &ArgMatches) -> Result<Self, Error> {
+        let stdout = match matches.get_one::<String>("expect-stdout") {
+            Some(filename) => {
+                let content = std::fs::read_to_string(filename).map_err(|source| Error::Io {
+                    path: filename.clone(),
+                    source,
+                })?;
+                Some((filename.clone(), content))
+            }
+            None => None,
+        };
+        let regex_arg = |id| {
+            matches
+                .get_one::<String>(id)
+                .map(|re| new_regex(re))
+                .transpose()
+        };
+
+        Ok(ExecExpectations {
+            stdout,
+            stdout_re: regex_arg("expect-stdout-regex")?,
+            stderr_re: regex_arg("expect-stderr-regex")?,
+            exit_code: matches.get_one::<i32>("expect-exit-code").copied(),
+        })
+    }
+
+    /// True when no assertion was given.
+    pub fn is_empty(&self) -> bool {
+        self.stdout.is_none()
+            && self.stdout_re.is_none()
+            && self.stderr_re.is_none()
+            && self.exit_code.is_none()
+    }
+
+    /// Checks `exec_result` and returns a description of each failed
+    /// assertion. The exit code is expected to be 0 unless
+    /// `--expect-exit-code` says otherwise.
+    pub fn check(&self, exec_result: Option<&ExecutionResult>) -> Vec<String> {
+        let Some(exec_result) = exec_result else {
+            return vec!["program was not executed".to_string()];
+        };
+        let mut failures = Vec::new();
+
+        let expected_code = self.exit_code.unwrap_or(0);
+        if exec_result.code != expected_code {
+            failures.push(format!(
+                "exit code is {}, expected {}",
+                exec_result.code, expected_code
+            ));
+        }
+
+        let stdout = exec_result.stdout.to_text();
+        if let Some((filename, expected)) = &self.stdout {
+            // Compiler-Explorer splits the output in lines, the final
+            // newline of the file is lost on the way.
+            let expected = expected.trim_end_matches('\n');
+            if stdout != expected {
+                failures.push(format!(
+                    "stdout differs from {}:\n{}",
+                    filename,
+                    unified_diff(expected, &stdout, filename, "stdout")
+                ));
+            }
+        }
+        if let Some(re) = &self.stdout_re {
+            if !re.is_match(&stdout) {
+                failures.push(format!("stdout doesn't match \"{}\"", re));
+            }
+        }
+        if let Some(re) = &self.stderr_re {
+            if !re.is_match(&exec_result.stderr.to_text()) {
+                failures.push(format!("stderr doesn't match \"{}\"", re));
+            }
+        }
+        failures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expectations() -> ExecExpectations {
+        ExecExpectations {
+            stdout: None,
+            stdout_re: None,
+            stderr_re: None,
+            exit_code: None,
+        }
+    }
+
+    fn exec_result(code: i32, stdout: &[&str], stderr: &[&str]) -> ExecutionResult {
+        let lines = |lines: &[&str]| {
+            lines
+                .iter()
+                .map(|l| serde_json::json!({ "text": l }))
+                .collect::<Vec<_>>()
+        };
+        serde_json::from_value(serde_json::json!({
+            "code": code,
+            "timedOut": false,
+            "stdout": lines(stdout),
+            "stderr": lines(stderr),
+            "didExecute": true,
+            "buildResult": {
+                "inputFilename": "example.c",
+                "code": 0,
+                "okToCache": true,
+                "timedOut": false,
+                "stdout": [],
+                "stderr": [],
+                "truncated": false,
+                "execTime": "1",
+                "downloads": [],
+                "executableFilename": "output.s",
+                "compilationOptions": [],
+            },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn not_executed() {
+        assert_eq!(expectations().check(None), ["program was not executed"]);
+    }
+
+    #[test]
+    fn exit_code() {
+        let result = exec_result(3, &[], &[]);
+        assert_eq!(
+            expectations().check(Some(&result)),
+            ["exit code is 3, expected 0"]
+        );
+
+        let expected_3 = ExecExpectations {
+            exit_code: Some(3),
+            ..expectations()
+        };
+        assert!(expected_3.check(Some(&result)).is_empty());
+        assert_eq!(
+            expected_3.check(Some(&exec_result(0, &[], &[]))),
+            ["exit code is 0, expected 3"]
+        );
+    }
+
+    #[test]
+    fn stdout_ignores_final_newline() {
+        let expected = ExecExpectations {
+            stdout: Some(("out.txt".to_string(), "Hello\nWorld\n".to_string())),
+            ..expectations()
+        };
+        let result = exec_result(0, &["Hello", "World"], &[]);
+        assert!(expected.check(Some(&result)).is_empty());
+    }
+
+    #[test]
+    fn stdout_mismatch_has_diff() {
+        let expected = ExecExpectations {
+            stdout: Some(("out.txt".to_string(), "Hello\nWorld\n".to_string())),
+            ..expectations()
+        };
+        let failures = expected.check(Some(&exec_result(0, &["Hello", "there"], &[])));
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0].starts_with("stdout differs from out.txt:\n"));
+        assert!(failures[0].contains("-World"));
+        assert!(failures[0].contains("+there"));
+    }
+
+    #[test]
+    fn output_regexes() {
+        let expected = ExecExpectations {
+            stdout_re: Some(Regex::new("^Hello").unwrap()),
+            stderr_re: Some(Regex::new("warning").unwrap()),
+            ..expectations()
+        };
+        assert!(expected
+            .check(Some(&exec_result(0, &["Hello"], &["a warning"])))
+            .is_empty());
+        assert_eq!(
+            expected.check(Some(&exec_result(0, &["Bye"], &["an error"]))),
+            [
+                "stdout doesn't match \"^Hello\"",
+                "stderr doesn't match \"warning\""
+            ]
+        );
+    }
+
+    #[test]
+    fn all_failures_reported() {
+        let expected = ExecExpectations {
+            stdout_re: Some(Regex::new("ok").unwrap()),
+            exit_code: Some(1),
+            ..expectations()
+        };
+        let failures = expected.check(Some(&exec_result(0, &["ko"], &[])));
+        assert_eq!(
+            failures,
+            ["exit code is 0, expected 1", "stdout doesn't match \"ok\""]
+        );
+    }
+}
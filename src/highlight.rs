@@ -0,0 +1,356 @@
+/*
+ * This file is part of the ce-rs (https://github.com/dkm/ce-rs)
+ * Copyright (c) 2023 Marc Poulhiès <dkm@kataplop.net>.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use ce_api::types::AsmOutputItem;
+use colored::*;
+use regex::Regex;
+
+const X86_REGISTERS: &str = r"[re]?[abcd]x|[abcd][lh]|[re]?(?:si|di|sp|bp|ip)|(?:si|di|sp|bp)l|r(?:[89]|1[0-5])[dwb]?|[xyz]mm(?:[12]?\d|3[01])|k[0-7]|st|[cdefgs]s";
+const AARCH64_REGISTERS: &str =
+    r"[xwbhsdqv](?:[12]?\d|3[01])(?:\.\d*[bhsdq])?|sp|wsp|xzr|wzr|lr|fp|pc|nzcv";
+const ARM32_REGISTERS: &str =
+    r"r(?:1[0-5]|\d)|[sd](?:[12]?\d|3[01])|q(?:1[0-5]|\d)|sp|lr|pc|fp|ip|sl|[ac]psr";
+const RISCV_REGISTERS: &str = r"x(?:[12]?\d|3[01])|f(?:[12]?\d|3[01])|zero|ra|sp|gp|tp|t[0-6]|s(?:1[01]|\d)|a[0-7]|ft(?:1[01]|\d)|fs(?:1[01]|\d)|fa[0-7]";
+const POWERPC_REGISTERS: &str =
+    r"(?:r|f|v|vs)(?:[12]?\d|3[01])|vs(?:[3-5]\d|6[0-3])|cr[0-7]|lr|ctr|xer";
+const MIPS_REGISTERS: &str =
+    r"\$(?:zero|at|v[01]|a[0-3]|t\d|s[0-8]|k[01]|gp|sp|fp|ra|f?(?:[12]?\d|3[01]))";
+
+/// Syntax details of an instruction set.
+struct Isa {
+    /// Alternation matching the register names.
+    registers: Option<&'static str>,
+    /// What starts a comment.
+    comments: &'static [&'static str],
+}
+
+impl Isa {
+    /// `instruction_set` is `CompilerInfo.instructionSet`. Unknown instruction
+    /// sets still get mnemonics, labels, directives and numbers highlighted.
+    fn new(instruction_set: &str) -> Self {
+        let (registers, comments): (_, &[&str]) = match instruction_set {
+            "amd64" | "x86" => (Some(X86_REGISTERS), &["#", ";"]),
+            "aarch64" => (Some(AARCH64_REGISTERS), &["//", ";"]),
+            "arm32" => (Some(ARM32_REGISTERS), &["@", "//", ";"]),
+            "riscv32" | "riscv64" => (Some(RISCV_REGISTERS), &["#"]),
+            "powerpc" => (Some(POWERPC_REGISTERS), &["#"]),
+            "mips" => (Some(MIPS_REGISTERS), &["#"]),
+            _ => (None, &["#", ";", "//"]),
+        };
+        Isa {
+            registers,
+            comments,
+        }
+    }
+}
+
+/// ANSI highlighter for assembly lines. Colors follow the `colored` settings,
+/// so they are disabled with `NO_COLOR` or when stdout isn't a terminal.
+pub struct Highlighter {
+    isa: Isa,
+    label_def: Regex,
+    operands: Regex,
+}
+
+impl Highlighter {
+    /// `intel` is `Filters.intel`, only meaningful for x86 where AT&T
+    /// registers and immediates have a `%` and `$` prefix.
+    pub fn new(instruction_set: &str, intel: bool) -> Self {
+        let isa = Isa::new(instruction_set);
+        let is_x86 = matches!(instruction_set, "amd64" | "x86");
+
+        let mut groups = vec![r#"(?P<str>"(?:[^"\\]|\\.)*")"#.to_string()];
+        match isa.registers {
+            Some(regs) if is_x86 && !intel => groups.push(format!(r"(?P<reg>%(?i:{})\b)", regs)),
+            Some(regs) if regs.starts_with(r"\$") => {
+                groups.push(format!(r"(?P<reg>(?:{})\b)", regs))
+            }
+            Some(regs) => groups.push(format!(r"(?P<reg>\b(?i:{})\b)", regs)),
+            None => (),
+        }
+        match instruction_set {
+            _ if is_x86 && !intel => groups.push(r"(?P<imm>\$-?[\w.]+)".to_string()),
+            "aarch64" | "arm32" => groups.push(r"(?P<imm>#-?(?:0x[0-9a-fA-F]+|\d+))".to_string()),
+            _ => (),
+        }
+        groups.push(r"(?P<num>-?\b(?:0x[0-9a-fA-F]+|\d+)\b)".to_string());
+        groups.push(r"(?P<sym>[A-Za-z_.$][\w.$@]*)".to_string());
+
+        Highlighter {
+            isa,
+            label_def: Regex::new(r#"^(?:[\w.$@]+|"[^"]*"):"#).unwrap(),
+            operands: Regex::new(&groups.join("|")).unwrap(),
+        }
+    }
+
+    pub fn highlight(&self, item: &AsmOutputItem) -> String {
+        let (code, comment) = self.split_comment(&item.text);
+        let trimmed = code.trim_start();
+        let mut out = code[..code.len() - trimmed.len()].to_string();
+
+        let mut rest = trimmed;
+        if let Some(m) = self.label_def.find(rest) {
+            out.push_str(&m.as_str().green().bold().to_string());
+            rest = &rest[m.end()..];
+        }
+
+        let body = rest.trim_start();
+        out.push_str(&rest[..rest.len() - body.len()]);
+        let word_end = body.find(char::is_whitespace).unwrap_or(body.len());
+        let (word, operands) = body.split_at(word_end);
+        if word.starts_with('.') {
+            out.push_str(&word.yellow().to_string());
+        } else if !word.is_empty() {
+            out.push_str(&word.blue().bold().to_string());
+        }
+        out.push_str(&self.highlight_operands(operands, item));
+
+        if !comment.is_empty() {
+            out.push_str(&comment.bright_black().to_string());
+        }
+        out
+    }
+
+    fn highlight_operands(&self, operands: &str, item: &AsmOutputItem) -> String {
+        let mut out = String::new();
+        let mut last = 0;
+        for caps in self.operands.captures_iter(operands) {
+            let m = caps.get(0).unwrap();
+            out.push_str(&operands[last..m.start()]);
+            let text = m.as_str();
+            let colored = if caps.name("str").is_some() {
+                text.green()
+            } else if caps.name("reg").is_some() {
+                text.cyan()
+            } else if caps.name("imm").is_some() || caps.name("num").is_some() {
+                text.magenta()
+            } else if text.starts_with(".L") || item.labels.iter().any(|l| l.name == text) {
+                text.green()
+            } else {
+                text.normal()
+            };
+            out.push_str(&colored.to_string());
+            last = m.end();
+        }
+        out.push_str(&operands[last..]);
+        out
+    }
+
+    /// Splits `line` before the first comment marker outside of a string.
+    fn split_comment<'a>(&self, line: &'a str) -> (&'a str, &'a str) {
+        let mut in_string = false;
+        let mut escaped = false;
+        for (i, c) in line.char_indices() {
+            if in_string {
+                match c {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = true,
+                    '"' => in_string = false,
+                    _ => (),
+                }
+            } else if c == '"' {
+                in_string = true;
+            } else if self.isa.comments.iter().any(|m| line[i..].starts_with(m)) {
+                return line.split_at(i);
+            }
+        }
+        (line, "")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn highlight(instruction_set: &str, intel: bool, text: &str, labels: &[&str]) -> String {
+        // Colors are what is under test: force them, whatever the terminal.
+        colored::control::set_override(true);
+        let labels = labels
+            .iter()
+            .map(|name| serde_json::json!({"name": name, "range": {"startCol": 0, "endCol": 0}}))
+            .collect::<Vec<_>>();
+        let item: AsmOutputItem = serde_json::from_value(serde_json::json!({
+            "text": text,
+            "source": null,
+            "labels": labels,
+        }))
+        .unwrap();
+        Highlighter::new(instruction_set, intel).highlight(&item)
+    }
+
+    fn mnemonic(s: &str) -> String {
+        s.blue().bold().to_string()
+    }
+
+    fn reg(s: &str) -> String {
+        s.cyan().to_string()
+    }
+
+    fn num(s: &str) -> String {
+        s.magenta().to_string()
+    }
+
+    fn comment(s: &str) -> String {
+        s.bright_black().to_string()
+    }
+
+    #[test]
+    fn x86_att() {
+        assert_eq!(
+            highlight("amd64", false, "  movl $1, %eax # one", &[]),
+            format!(
+                "  {} {}, {} {}",
+                mnemonic("movl"),
+                num("$1"),
+                reg("%eax"),
+                comment("# one")
+            )
+        );
+        // Without the `%` prefix, eax is a symbol in AT&T syntax.
+        assert_eq!(
+            highlight("amd64", false, "  call eax", &[]),
+            format!("  {} eax", mnemonic("call"))
+        );
+    }
+
+    #[test]
+    fn x86_intel() {
+        assert_eq!(
+            highlight("x86", true, "  mov DWORD PTR [rbp-4], 0x10 ; store", &[]),
+            format!(
+                "  {} DWORD PTR [{}{}], {} {}",
+                mnemonic("mov"),
+                reg("rbp"),
+                num("-4"),
+                num("0x10"),
+                comment("; store")
+            )
+        );
+    }
+
+    #[test]
+    fn aarch64() {
+        assert_eq!(
+            highlight("aarch64", false, "  add w0, w1, #4 // sum", &[]),
+            format!(
+                "  {} {}, {}, {} {}",
+                mnemonic("add"),
+                reg("w0"),
+                reg("w1"),
+                num("#4"),
+                comment("// sum")
+            )
+        );
+    }
+
+    #[test]
+    fn arm32() {
+        assert_eq!(
+            highlight("arm32", false, "  mov r0, #1 @ ret", &[]),
+            format!(
+                "  {} {}, {} {}",
+                mnemonic("mov"),
+                reg("r0"),
+                num("#1"),
+                comment("@ ret")
+            )
+        );
+    }
+
+    #[test]
+    fn riscv() {
+        assert_eq!(
+            highlight("riscv64", false, "  addi a0, sp, 16 # frame", &[]),
+            format!(
+                "  {} {}, {}, {} {}",
+                mnemonic("addi"),
+                reg("a0"),
+                reg("sp"),
+                num("16"),
+                comment("# frame")
+            )
+        );
+    }
+
+    #[test]
+    fn powerpc() {
+        assert_eq!(
+            highlight("powerpc", false, "  li r3, 0", &[]),
+            format!("  {} {}, {}", mnemonic("li"), reg("r3"), num("0"))
+        );
+    }
+
+    #[test]
+    fn mips() {
+        assert_eq!(
+            highlight("mips", false, "  addiu $sp, $sp, -8", &[]),
+            format!(
+                "  {} {}, {}, {}",
+                mnemonic("addiu"),
+                reg("$sp"),
+                reg("$sp"),
+                num("-8")
+            )
+        );
+    }
+
+    #[test]
+    fn unknown_isa() {
+        assert_eq!(
+            highlight("ebpf", false, "  exit ; done", &[]),
+            format!("  {} {}", mnemonic("exit"), comment("; done"))
+        );
+    }
+
+    #[test]
+    fn labels() {
+        assert_eq!(
+            highlight("amd64", true, "main:", &[]),
+            "main:".green().bold().to_string()
+        );
+        assert_eq!(
+            highlight("amd64", true, "  jmp .L2", &[]),
+            format!("  {} {}", mnemonic("jmp"), ".L2".green())
+        );
+        assert_eq!(
+            highlight("amd64", true, "  call square", &["square"]),
+            format!("  {} {}", mnemonic("call"), "square".green())
+        );
+    }
+
+    #[test]
+    fn directives() {
+        assert_eq!(
+            highlight("amd64", true, "  .string \"a # b\" # text", &[]),
+            format!(
+                "  {} {} {}",
+                ".string".yellow(),
+                "\"a # b\"".green(),
+                comment("# text")
+            )
+        );
+        assert_eq!(
+            highlight("amd64", true, ".LC0: .long 42", &[]),
+            format!(
+                "{} {} {}",
+                ".LC0:".green().bold(),
+                ".long".yellow(),
+                num("42")
+            )
+        );
+    }
+}
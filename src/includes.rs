@@ -0,0 +1,291 @@
+/*
+ * This file is part of the ce-rs (https://github.com/dkm/ce-rs)
+ * Copyright (c) 2023 Marc Poulhiès <dkm@kataplop.net>.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::Error;
+use ce_api::types::FiledataPair;
+use regex::Regex;
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+/// A quoted `#include`, the included name being the first group.
+const INCLUDE_RE: &str = r#"(?m)^\s*#\s*include\s*"([^"]+)""#;
+
+/// A file to scan: its content, the directory it was read from and its
+/// name relative to the main source.
+struct Pending {
+    contents: String,
+    dir: PathBuf,
+    filename: String,
+}
+
+/// Joins `name` to the directory of `filename` and resolves `.` and `..`.
+/// `None` when the result is absolute or goes above the main source, where
+/// Compiler-Explorer can't put a file.
+fn relative_name(filename: &str, name: &str) -> Option<String> {
+    let joined = Path::new(filename)
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(name);
+    let mut parts: Vec<String> = Vec::new();
+    for component in joined.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            Component::CurDir => (),
+            Component::ParentDir => {
+                parts.pop()?;
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// Local headers included by `source`, directly or not, to send along it as
+/// extra files. A quoted `#include` is looked up like the compiler does: in
+/// the directory of the including file, then in `include_dirs`.
+///
+/// Returns the headers and the includes that couldn't be found or can't be
+/// sent (absolute paths or paths above the main source).
+pub fn bundle_includes(
+    source: &str,
+    source_dir: &Path,
+    include_dirs: &[PathBuf],
+) -> Result<(Vec<FiledataPair>, Vec<String>), Error> {
+    let include_re = Regex::new(INCLUDE_RE).unwrap();
+    let mut files = Vec::new();
+    let mut unresolved = Vec::new();
+    let mut seen = HashSet::new();
+    let mut queue = vec![Pending {
+        contents: source.to_string(),
+        dir: source_dir.to_path_buf(),
+        filename: String::new(),
+    }];
+
+    while let Some(current) = queue.pop() {
+        for caps in include_re.captures_iter(&current.contents) {
+            let name = &caps[1];
+            let found = if current.dir.join(name).is_file() {
+                Some((
+                    current.dir.join(name),
+                    relative_name(&current.filename, name),
+                ))
+            } else {
+                include_dirs
+                    .iter()
+                    .map(|dir| dir.join(name))
+                    .find(|path| path.is_file())
+                    .map(|path| (path, relative_name("", name)))
+            };
+
+            let Some((path, Some(filename))) = found else {
+                if !unresolved.iter().any(|u| u == name) {
+                    unresolved.push(name.to_string());
+                }
+                continue;
+            };
+            if !seen.insert(filename.clone()) {
+                continue;
+            }
+
+            let contents = std::fs::read_to_string(&path).map_err(|source| Error::Io {
+                path: path.display().to_string(),
+                source,
+            })?;
+            queue.push(Pending {
+                contents: contents.clone(),
+                dir: path.parent().unwrap_or(Path::new("")).to_path_buf(),
+                filename: filename.clone(),
+            });
+            files.push(FiledataPair { filename, contents });
+        }
+    }
+    Ok((files, unresolved))
+}
+
+/// `source` with its includes of `files` (as returned by `bundle_includes()`)
+/// replaced by their content, each file once. This gives a single source
+/// for places that can't hold extra files, like a shared session.
+pub fn inline_includes(source: &str, files: &[FiledataPair]) -> String {
+    let include_re = Regex::new(INCLUDE_RE).unwrap();
+    let mut seen = HashSet::new();
+    inline(&include_re, source, "", files, &mut seen)
+}
+
+fn inline(
+    include_re: &Regex,
+    contents: &str,
+    filename: &str,
+    files: &[FiledataPair],
+    seen: &mut HashSet<String>,
+) -> String {
+    let mut out = String::new();
+    for line in contents.split_inclusive('\n') {
+        // Each file is inlined once anyway, and the directive would be
+        // reported in the main source.
+        if !filename.is_empty() && line.split_whitespace().collect::<String>() == "#pragmaonce" {
+            continue;
+        }
+        // Same lookup as bundle_includes(): next to the including file, then
+        // in the include directories, whose files are named from their root.
+        let file = include_re.captures(line).and_then(|caps| {
+            [
+                relative_name(filename, &caps[1]),
+                relative_name("", &caps[1]),
+            ]
+            .into_iter()
+            .flatten()
+            .find_map(|name| files.iter().find(|f| f.filename == name))
+        });
+        let Some(file) = file else {
+            out.push_str(line);
+            continue;
+        };
+        if seen.insert(file.filename.clone()) {
+            out.push_str(&inline(
+                include_re,
+                &file.contents,
+                &file.filename,
+                files,
+                seen,
+            ));
+            if !out.ends_with('\n') {
+                out.push('\n');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fresh directory for one test, with the given files.
+    fn tree(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("ce-rs-{}-{}", test, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for (name, contents) in files {
+            let path = root.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        root
+    }
+
+    fn names(files: &[FiledataPair]) -> Vec<&str> {
+        let mut names = files
+            .iter()
+            .map(|f| f.filename.as_str())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn relative_names() {
+        assert_eq!(relative_name("", "a.h").as_deref(), Some("a.h"));
+        assert_eq!(relative_name("inc/a.h", "b.h").as_deref(), Some("inc/b.h"));
+        assert_eq!(relative_name("inc/a.h", "../b.h").as_deref(), Some("b.h"));
+        assert_eq!(
+            relative_name("inc/a.h", "./b.h").as_deref(),
+            Some("inc/b.h")
+        );
+        assert_eq!(relative_name("", "../b.h"), None);
+        assert_eq!(relative_name("inc/a.h", "../../b.h"), None);
+        assert_eq!(relative_name("", "/usr/include/b.h"), None);
+    }
+
+    #[test]
+    fn nested_includes() {
+        let root = tree(
+            "nested",
+            &[
+                ("src/local.h", "#include \"sub/a.h\"\n"),
+                ("src/sub/a.h", "  #  include \"b.h\"\n#include <vector>\n"),
+                ("src/sub/b.h", "int b;\n"),
+            ],
+        );
+        let (files, unresolved) =
+            bundle_includes("#include \"local.h\"\n", &root.join("src"), &[]).unwrap();
+        assert_eq!(names(&files), ["local.h", "sub/a.h", "sub/b.h"]);
+        assert!(unresolved.is_empty());
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn including_dir_before_include_dirs() {
+        let root = tree(
+            "order",
+            &[
+                ("src/a.h", "int from_src;\n"),
+                ("inc/a.h", "int from_inc;\n"),
+                ("inc/only.h", "int only;\n"),
+            ],
+        );
+        let source = "#include \"a.h\"\n#include \"only.h\"\n";
+        let (files, _) = bundle_includes(source, &root.join("src"), &[root.join("inc")]).unwrap();
+        assert_eq!(names(&files), ["a.h", "only.h"]);
+        let a = files.iter().find(|f| f.filename == "a.h").unwrap();
+        assert_eq!(a.contents, "int from_src;\n");
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn each_header_once() {
+        let root = tree(
+            "dedup",
+            &[
+                ("src/a.h", "#include \"b.h\"\n#include \"a.h\"\n"),
+                ("src/b.h", "#include \"a.h\"\n"),
+            ],
+        );
+        let source = "#include \"a.h\"\n#include \"b.h\"\n#include \"a.h\"\n";
+        let (files, _) = bundle_includes(source, &root.join("src"), &[]).unwrap();
+        assert_eq!(names(&files), ["a.h", "b.h"]);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn inlined_includes() {
+        let files = [
+            FiledataPair {
+                filename: "sub/a.h".to_string(),
+                contents: "# pragma  once\n#include \"b.h\"\nint a;".to_string(),
+            },
+            FiledataPair {
+                filename: "sub/b.h".to_string(),
+                contents: "#include \"a.h\"\nint b;\n".to_string(),
+            },
+        ];
+        let source = "#include \"sub/a.h\"\n#include <stdio.h>\n#include \"sub/b.h\"\n#include \"missing.h\"\nint main() {}\n";
+        assert_eq!(
+            inline_includes(source, &files),
+            "int b;\nint a;\n#include <stdio.h>\n#include \"missing.h\"\nint main() {}\n"
+        );
+        assert_eq!(inline_includes(source, &[]), source);
+    }
+
+    #[test]
+    fn unresolvable_includes() {
+        let root = tree("unresolved", &[("up.h", "int up;\n"), ("src/main.h", "")]);
+        let source = "#include \"missing.h\"\n#include \"../up.h\"\n#include \"missing.h\"\n";
+        let (files, unresolved) = bundle_includes(source, &root.join("src"), &[]).unwrap();
+        assert!(files.is_empty());
+        assert_eq!(unresolved, ["missing.h", "../up.h"]);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
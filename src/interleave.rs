@@ -0,0 +1,222 @@
+/*
+ * This file is part of the ce-rs (https://github.com/dkm/ce-rs)
+ * Copyright (c) 2023 Marc Poulhiès <dkm@kataplop.net>.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use ce_api::types::{AsmOutputItem, FiledataPair};
+use colored::*;
+use std::collections::BTreeMap;
+
+/// Colors given to source lines and their assembly, cycled over like in the
+/// web UI.
+const PALETTE: [Color; 10] = [
+    Color::Cyan,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::BrightCyan,
+    Color::BrightGreen,
+    Color::BrightYellow,
+    Color::BrightBlue,
+    Color::BrightMagenta,
+];
+
+/// Prints each line of `source` followed by the assembly lines generated for
+/// it. `file` is the file `source` comes from (`None` for the main source), as
+/// found in the `SourceLocation` of `asm` items.
+///
+/// Assembly lines without a location in `file` (labels, directives, code
+/// from other files) are attached to the next mapped line, or printed at the
+/// end when there is none.
+pub fn interleave(source: &str, file: Option<&str>, asm: &[AsmOutputItem]) -> String {
+    let mut by_line: BTreeMap<usize, Vec<&str>> = BTreeMap::new();
+    let mut pending = Vec::new();
+
+    for item in asm {
+        match &item.source {
+            Some(loc) if loc.line > 0 && loc.file.as_deref() == file => {
+                let lines = by_line.entry(loc.line as usize).or_default();
+                lines.append(&mut pending);
+                lines.push(&item.text);
+            }
+            _ => pending.push(&item.text),
+        }
+    }
+
+    let mut out = Vec::new();
+    for (i, line) in source.lines().enumerate() {
+        let line_no = i + 1;
+        let header = format!("{:>4} | {}", line_no, line);
+        match by_line.get(&line_no) {
+            Some(asm_lines) => {
+                let color = PALETTE[line_no % PALETTE.len()];
+                out.push(header.color(color).bold().to_string());
+                for asm_line in asm_lines {
+                    out.push(format!("       {}", asm_line.color(color)));
+                }
+            }
+            None => out.push(header.dimmed().to_string()),
+        }
+    }
+    out.extend(
+        pending
+            .iter()
+            .map(|asm_line| format!("       {}", asm_line)),
+    );
+    out.join("\n")
+}
+
+/// Prints `asm` in order, each run of lines generated for the same source line
+/// preceded by that line and the name of its file. Used for multi-file
+/// compilations, where `main` is the name and content of the main source
+/// (`None` for CMake projects) and `files` are the other files.
+pub fn interleave_files(
+    main: Option<(&str, &str)>,
+    files: &[FiledataPair],
+    asm: &[AsmOutputItem],
+) -> String {
+    let mut out = Vec::new();
+    let mut current = None;
+    let mut color = Color::White;
+
+    for item in asm {
+        let Some(loc) = item.source.as_ref().filter(|loc| loc.line > 0) else {
+            out.push(format!("       {}", item.text));
+            continue;
+        };
+        let location = (loc.file.as_deref(), loc.line);
+        if current != Some(location) {
+            current = Some(location);
+            color = PALETTE[loc.line as usize % PALETTE.len()];
+
+            let (name, content) = match loc.file.as_deref() {
+                None => main.unwrap_or(("<source>", "")),
+                Some(file) => files
+                    .iter()
+                    .find(|f| file == f.filename || file.ends_with(&format!("/{}", f.filename)))
+                    .map_or((file, ""), |f| (f.filename.as_str(), f.contents.as_str())),
+            };
+            let line = content.lines().nth(loc.line as usize - 1).unwrap_or("");
+            let header = format!("{}:{} | {}", name, loc.line, line);
+            out.push(header.color(color).bold().to_string());
+        }
+        out.push(format!("       {}", item.text.color(color)));
+    }
+    out.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ce_api::types::SourceLocation;
+    use regex::Regex;
+
+    /// `out` without its colors, whether the global `colored` settings enabled
+    /// them or not.
+    fn uncolored(out: String) -> String {
+        Regex::new(r"\x1b\[[0-9;]*m")
+            .unwrap()
+            .replace_all(&out, "")
+            .into_owned()
+    }
+
+    fn asm(text: &str, file: Option<&str>, line: i32) -> AsmOutputItem {
+        AsmOutputItem {
+            text: text.to_string(),
+            source: (line != 0).then(|| SourceLocation {
+                file: file.map(str::to_string),
+                line,
+            }),
+            labels: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn asm_follows_its_line() {
+        let source = "int f() {\n  return 1;\n}";
+        let items = [
+            asm("f:", None, 0),
+            asm("  mov eax, 1", None, 2),
+            asm("  ret", None, 3),
+            asm(".section .note", None, 0),
+        ];
+        assert_eq!(
+            uncolored(interleave(source, None, &items)),
+            [
+                "   1 | int f() {",
+                "   2 |   return 1;",
+                "       f:",
+                "         mov eax, 1",
+                "   3 | }",
+                "         ret",
+                "       .section .note",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn other_files_attached_to_next_line() {
+        let source = "#include \"a.h\"\nint x = a();";
+        let items = [
+            asm("  call a", Some("a.h"), 1),
+            asm("  mov x, eax", None, 2),
+        ];
+        assert_eq!(
+            uncolored(interleave(source, None, &items)),
+            [
+                "   1 | #include \"a.h\"",
+                "   2 | int x = a();",
+                "         call a",
+                "         mov x, eax",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn files_named_in_headers() {
+        let files = [FiledataPair {
+            filename: "inc/a.h".to_string(),
+            contents: "int a() { return 1; }".to_string(),
+        }];
+        let items = [
+            asm("a():", None, 0),
+            asm("  mov eax, 1", Some("/tmp/build/inc/a.h"), 1),
+            asm("  ret", Some("/tmp/build/inc/a.h"), 1),
+            asm("  call a()", None, 2),
+            asm("  call puts", Some("/usr/include/stdio.h"), 12),
+        ];
+        assert_eq!(
+            uncolored(interleave_files(
+                Some(("main.cpp", "#include \"inc/a.h\"\nint x = a();")),
+                &files,
+                &items
+            )),
+            [
+                "       a():",
+                "inc/a.h:1 | int a() { return 1; }",
+                "         mov eax, 1",
+                "         ret",
+                "main.cpp:2 | int x = a();",
+                "         call a()",
+                "/usr/include/stdio.h:12 | ",
+                "         call puts",
+            ]
+            .join("\n")
+        );
+    }
+}
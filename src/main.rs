@@ -15,349 +15,1376 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+mod diff;
+mod expect;
+mod highlight;
+mod includes;
+mod interleave;
+mod report;
+
+use ce_api::types::*;
+use ce_api::{CacheConfig, Client};
 use clap::{Arg, ArgGroup, ArgMatches, Command};
 use colored::*;
+use expect::{expect_args, ExecExpectations};
+use futures::stream::{self, StreamExt};
+use highlight::Highlighter;
 use regex::Regex;
+use report::CompileReport;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use thiserror::Error;
-mod types;
-use types::*;
-use version_compare::{compare, compare_to, Cmp, Version};
+use version_compare::{Part, Version};
+
+/// Upper bound for `--jobs`, to avoid hammering the Compiler-Explorer instance.
+const MAX_JOBS: i64 = 8;
+
+/// Exit code when compilations or executions didn't have the expected outcome.
+const EXIT_FAILURE: i32 = 1;
+/// Exit code for invalid command lines (same as clap's own usage errors).
+const EXIT_USAGE: i32 = 2;
+/// Exit code when talking to the Compiler-Explorer instance failed.
+const EXIT_NETWORK: i32 = 3;
+/// Exit code when reading or writing local files failed.
+const EXIT_IO: i32 = 4;
+/// Exit code when the bisected range doesn't hold a regression.
+const EXIT_NO_REGRESSION: i32 = 5;
 
 #[derive(Debug, Error)]
 enum Error {
-    #[error("Reqwest error")]
-    ReqwestError {
+    #[error(transparent)]
+    Api {
         #[from]
-        source: reqwest::Error,
+        source: ce_api::Error,
     },
 
-    #[error("Serde error")]
-    SerdeError {
-        #[from]
-        source: serde_json::Error,
+    #[error("Unknown compiler id: {id}")]
+    UnknownCompiler { id: String },
+
+    #[error("Unknown language id: {id}")]
+    UnknownLanguage { id: String },
+
+    #[error("Extension {extension} matches several languages ({candidates}), use --language")]
+    AmbiguousLanguage {
+        extension: String,
+        candidates: String,
     },
 
-    #[error("Internal Error")]
-    InternalError,
-}
+    #[error("Invalid regex \"{regex}\": {source}")]
+    InvalidRegex { regex: String, source: regex::Error },
 
-struct Session {
-    base_url: String,
-    client: reqwest::Client,
-    // all_compilers: Option<Vec<CompilerInfo>>,
-    // all_compilers_full: bool,
-}
+    #[error("Invalid version: {version}")]
+    InvalidVersion { version: String },
 
-async fn languages(session: &Session) -> Result<Vec<Language>, Error> {
-    let resp = session.client
-        .get(format!("{}/api/languages", session.base_url))
-        .header("Accept", "application/json")
-        .send()
-        .await?;
+    #[error("Invalid library \"{spec}\", expected <id>@<version>")]
+    InvalidLibrary { spec: String },
 
-    let resp = resp.json::<Vec<Language>>().await?;
-    Ok(resp)
-}
+    #[error("Unknown library {id} for language {language}")]
+    UnknownLibrary { id: String, language: String },
 
-async fn compilers(session: &Session, all_fields: bool) -> Result<Vec<CompilerInfo>, Error> {
-    let client = reqwest::Client::new();
+    #[error("Unknown version {version} of library {id}, available: {available}")]
+    UnknownLibraryVersion {
+        id: String,
+        version: String,
+        available: String,
+    },
 
-    let params = [("fields", (if all_fields { "all" } else { "no" }))];
+    #[error("Unknown tool {id} for language {language}")]
+    UnknownTool { id: String, language: String },
 
-    let url = if all_fields {
-        reqwest::Url::parse_with_params(&format!("{}/api/compilers", session.base_url), &params)
-            .unwrap()
-    } else {
-        reqwest::Url::parse(&format!("{}/api/compilers", session.base_url)).unwrap()
-    };
+    #[error("Compiler \"{compiler}\" doesn't support filter(s): {filters}")]
+    UnsupportedFilters { compiler: String, filters: String },
 
-    let resp = client
-        .get(url)
-        .header("Accept", "application/json")
-        .send()
-        .await?;
+    #[error("No compiler found")]
+    NoCompiler,
 
-    let resp = resp.json::<Vec<CompilerInfo>>().await?;
-    Ok(resp)
-}
+    #[error("No source given, use --source, --source-file or a file argument")]
+    NoSource,
 
-async fn compilers_id(session: &Session, id: u8) -> Result<Vec<CompilerInfo>, Error> {
-    let resp = session.client
-        .get(format!("{}/api/compilers/{}", session.base_url, id))
-        .header("Accept", "application/json")
-        .send()
-        .await?;
+    #[error("Stdin can't be read both as the source and as the program input")]
+    StdinConflict,
 
-    let resp = resp.json::<Vec<CompilerInfo>>().await?;
-    Ok(resp)
-}
+    #[error("{dir} has no CMakeLists.txt, use --main to give the main file")]
+    ProjectMain { dir: String },
 
-async fn shortlinkinfo(session: &Session, shortlink: &str) -> Result<ShortLinkInfo, Error> {
-    let resp = session.client
-        .get(format!(
-            "{}/api/shortlinkinfo/{}",
-            session.base_url, shortlink
-        ))
-        .header("Accept", "application/json")
-        .send()
-        .await?;
+    #[error("diff needs two compilers and/or two --flags, got {compilers} compiler(s) and {flags} flag set(s)")]
+    DiffSides { compilers: usize, flags: usize },
 
-    let resp = resp.json::<ShortLinkInfo>().await?;
-    Ok(resp)
-}
+    #[error("Need at least 2 compilers to bisect, found {found}")]
+    BisectCandidates { found: usize },
 
-async fn compile(
-    session: &Session,
-    compiler_id: &str,
-    job: CompileJob,
-) -> Result<CompileJobResult, Error> {
-
-    let resp = session.client
-        .post(format!(
-            "{}/api/compiler/{}/compile",
-            session.base_url, compiler_id
-        ))
-        .header("Accept", "application/json")
-        .json(&job)
-        .send()
-        .await?;
+    #[error("Oldest compiler \"{compiler}\" is already bad, nothing to bisect")]
+    AlreadyBad { compiler: String },
+
+    #[error("Newest compiler \"{compiler}\" is still good, nothing to bisect")]
+    StillGood { compiler: String },
+
+    #[error("{unexpected} of {total} compilation(s) didn't have the expected outcome")]
+    Outcome { unexpected: usize, total: usize },
 
-    let resp = resp.json::<CompileJobResult>().await?;
-    Ok(resp)
+    #[error("{path}: {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
 }
 
-async fn get_compiler_info(session: &Session, compiler_id: &str) -> Option<CompilerInfo> {
-    if let Ok(all_compilers) = compilers(session, true).await {
-        let all = all_compilers
-            .into_iter()
-            .filter(|x| x.id == compiler_id)
-            .collect::<Vec<CompilerInfo>>();
-        return Some(all[0].clone());
+impl Error {
+    fn exit_code(&self) -> i32 {
+        match self {
+            Error::Api {
+                source: ce_api::Error::InvalidUrl { .. },
+            } => EXIT_USAGE,
+            Error::Api { .. } => EXIT_NETWORK,
+            Error::Io { .. } => EXIT_IO,
+            Error::Outcome { .. } => EXIT_FAILURE,
+            Error::AlreadyBad { .. } | Error::StillGood { .. } => EXIT_NO_REGRESSION,
+            Error::UnknownCompiler { .. }
+            | Error::UnknownLanguage { .. }
+            | Error::AmbiguousLanguage { .. }
+            | Error::InvalidRegex { .. }
+            | Error::InvalidVersion { .. }
+            | Error::InvalidLibrary { .. }
+            | Error::UnknownLibrary { .. }
+            | Error::UnknownLibraryVersion { .. }
+            | Error::UnknownTool { .. }
+            | Error::DiffSides { .. }
+            | Error::UnsupportedFilters { .. }
+            | Error::NoSource
+            | Error::StdinConflict
+            | Error::ProjectMain { .. }
+            | Error::BisectCandidates { .. }
+            | Error::NoCompiler => EXIT_USAGE,
+        }
     }
-    None
+}
+
+fn new_regex(regex: &str) -> Result<Regex, Error> {
+    Regex::new(regex).map_err(|source| Error::InvalidRegex {
+        regex: regex.to_string(),
+        source,
+    })
+}
+
+fn new_version(version: &str) -> Result<Version<'_>, Error> {
+    Version::from(version).ok_or_else(|| Error::InvalidVersion {
+        version: version.to_string(),
+    })
+}
+
+async fn get_compiler_info(client: &Client, compiler_id: &str) -> Result<CompilerInfo, Error> {
+    client
+        .compilers(true)
+        .await?
+        .into_iter()
+        .find(|x| x.id == compiler_id)
+        .ok_or_else(|| Error::UnknownCompiler {
+            id: compiler_id.to_string(),
+        })
 }
 
 async fn find_compilers(
-    session: &Session,
+    client: &Client,
     all_fields: bool,
     name: Option<String>,
     language: Option<String>,
     isa: Option<String>,
     version_min: Option<String>,
     version_max: Option<String>,
-) -> Option<Vec<CompilerInfo>> {
-    if let Ok(all_compilers) = compilers(session, all_fields).await {
-        let after_name_filtered = match name {
-            Some(n) => {
-                let re = Regex::new(format!(r"(?i){}", n).as_str()).unwrap();
-                all_compilers
-                    .into_iter()
-                    .filter(|x| re.captures(&x.name).is_some())
-                    .collect::<Vec<CompilerInfo>>()
-            }
-            _ => all_compilers,
-        };
+) -> Result<Vec<CompilerInfo>, Error> {
+    let all_compilers = client.compilers(all_fields).await?;
 
-        let after_lang_filtered = match language {
-            Some(lang) => {
-                // We use an exact match for lang
-                let re = Regex::new(format!(r"(?i)^{}$", lang).as_str()).unwrap();
-                after_name_filtered
-                    .into_iter()
-                    .filter(|x| re.captures(&x.lang).is_some())
-                    .collect::<Vec<CompilerInfo>>()
-            }
-            _ => after_name_filtered,
-        };
+    let after_name_filtered = match name {
+        Some(n) => {
+            let re = new_regex(&format!(r"(?i){}", n))?;
+            all_compilers
+                .into_iter()
+                .filter(|x| re.captures(&x.name).is_some())
+                .collect::<Vec<CompilerInfo>>()
+        }
+        _ => all_compilers,
+    };
 
-        let after_isa_filtered = match isa {
-            Some(misa) => {
-                // We use an exact match for ISA
-                let re = Regex::new(format!(r"(?i)^{}$", misa).as_str()).unwrap();
-                after_lang_filtered
-                    .into_iter()
-                    .filter(|x| re.captures(&x.instructionSet).is_some())
-                    .collect::<Vec<CompilerInfo>>()
-            }
-            _ => after_lang_filtered,
-        };
+    let after_lang_filtered = match language {
+        Some(lang) => {
+            // We use an exact match for lang
+            let re = new_regex(&format!(r"(?i)^{}$", lang))?;
+            after_name_filtered
+                .into_iter()
+                .filter(|x| re.captures(&x.lang).is_some())
+                .collect::<Vec<CompilerInfo>>()
+        }
+        _ => after_name_filtered,
+    };
 
-        let after_version_min_filtered = match version_min {
-            Some(vmin) => {
-                let vmin = Version::from(&vmin).unwrap();
-                after_isa_filtered
-                    .into_iter()
-                    .filter(|x| {
-                        let vcur = Version::from(&x.semver);
-                        vcur.is_some_and(|cur| cur >= vmin)
-                    })
-                    .collect::<Vec<CompilerInfo>>()
-            }
-            _ => after_isa_filtered,
+    let after_isa_filtered = match isa {
+        Some(misa) => {
+            // We use an exact match for ISA
+            let re = new_regex(&format!(r"(?i)^{}$", misa))?;
+            after_lang_filtered
+                .into_iter()
+                .filter(|x| re.captures(&x.instructionSet).is_some())
+                .collect::<Vec<CompilerInfo>>()
+        }
+        _ => after_lang_filtered,
+    };
+
+    let after_version_min_filtered = match version_min {
+        Some(vmin) => {
+            let vmin = new_version(&vmin)?;
+            after_isa_filtered
+                .into_iter()
+                .filter(|x| {
+                    let vcur = Version::from(&x.semver);
+                    vcur.is_some_and(|cur| cur >= vmin)
+                })
+                .collect::<Vec<CompilerInfo>>()
+        }
+        _ => after_isa_filtered,
+    };
+
+    let after_version_max_filtered = match version_max {
+        Some(vmax) => {
+            let vmax = new_version(&vmax)?;
+            after_version_min_filtered
+                .into_iter()
+                .filter(|x| {
+                    let vcur = Version::from(&x.semver);
+                    vcur.is_some_and(|cur| cur <= vmax)
+                })
+                .collect::<Vec<CompilerInfo>>()
+        }
+        _ => after_version_min_filtered,
+    };
+
+    Ok(after_version_max_filtered)
+}
+
+async fn get_language(client: &Client, language_id: &str) -> Result<Language, Error> {
+    client
+        .languages()
+        .await?
+        .into_iter()
+        .find(|x| x.id == language_id)
+        .ok_or_else(|| Error::UnknownLanguage {
+            id: language_id.to_string(),
+        })
+}
+
+async fn do_list_languages(client: &Client, _matches: &ArgMatches) -> Result<(), Error> {
+    let mut all_languages = client.languages().await?;
+    all_languages.sort_by_key(|a| a.name.to_lowercase());
+
+    for lang in all_languages {
+        println!("- {} (id: {})", lang.name, lang.id);
+    }
+    Ok(())
+}
+
+/// Compiler capabilities, flags, exclusions and ranking given with
+/// `selector_args()`.
+struct CompilerSelector {
+    supports: Vec<String>,
+    categories: Vec<Regex>,
+    group: Option<Regex>,
+    nightly: Option<bool>,
+    include_hidden: bool,
+    exclude_name: Option<Regex>,
+    exclude_ids: Vec<String>,
+    latest: Option<usize>,
+    latest_per_major: bool,
+}
+
+impl CompilerSelector {
+    fn from_matches(matches: &ArgMatches) -> Result<Self, Error> {
+        // We use an exact match for categories and groups
+        let exact = |s: &String| new_regex(&format!(r"(?i)^{}$", s));
+
+        let categories = matches
+            .get_many::<String>("category")
+            .unwrap_or_default()
+            .map(exact)
+            .collect::<Result<Vec<Regex>, Error>>()?;
+        let nightly = if matches.get_flag("nightly") {
+            Some(true)
+        } else if matches.get_flag("no-nightly") {
+            Some(false)
+        } else {
+            None
         };
 
-        let after_version_max_filtered = match version_max {
-            Some(vmax) => {
-                let vmax = Version::from(&vmax).unwrap();
-                after_version_min_filtered
-                    .into_iter()
-                    .filter(|x| {
-                        let vcur = Version::from(&x.semver);
-                        vcur.is_some_and(|cur| cur <= vmax)
-                    })
-                    .collect::<Vec<CompilerInfo>>()
+        Ok(CompilerSelector {
+            supports: matches
+                .get_many::<String>("supports")
+                .unwrap_or_default()
+                .cloned()
+                .collect(),
+            categories,
+            group: matches.get_one::<String>("group").map(exact).transpose()?,
+            nightly,
+            include_hidden: matches.get_flag("include-hidden"),
+            exclude_name: matches
+                .get_one::<String>("exclude-name")
+                .map(|n| new_regex(&format!(r"(?i){}", n)))
+                .transpose()?,
+            exclude_ids: matches
+                .get_many::<String>("exclude-id")
+                .unwrap_or_default()
+                .cloned()
+                .collect(),
+            latest: matches.get_one::<u32>("latest").map(|n| *n as usize),
+            latest_per_major: matches.get_flag("latest-per-major"),
+        })
+    }
+
+    /// True when a selector is given, hidden compilers aside.
+    fn is_set(&self) -> bool {
+        !self.supports.is_empty()
+            || !self.categories.is_empty()
+            || self.group.is_some()
+            || self.nightly.is_some()
+            || self.exclude_name.is_some()
+            || !self.exclude_ids.is_empty()
+            || self.latest.is_some()
+            || self.latest_per_major
+    }
+
+    /// Keeps the matching compilers of `compilers`, then the newest ones
+    /// with `--latest` and `--latest-per-major`. The latter keeps one
+    /// compiler per language, instruction set, compiler type and major
+    /// version, so all the rustc 1.x releases collapse into one.
+    fn select(&self, compilers: Vec<CompilerInfo>) -> Vec<CompilerInfo> {
+        let mut selected = compilers
+            .into_iter()
+            .filter(|c| self.matches(c))
+            .collect::<Vec<CompilerInfo>>();
+        if self.latest.is_none() && !self.latest_per_major {
+            return selected;
+        }
+
+        // Only compilers with a proper version can be ranked, trunk and
+        // nightlies without one are dropped.
+        selected.retain(|c| major_version(c).is_some());
+        selected.sort_by(compare_semver);
+
+        if self.latest_per_major {
+            // Majors are only comparable within a compiler family for a
+            // given language and target. Sorted by version, the newest of a
+            // major is the last one.
+            let family = |c: &CompilerInfo| {
+                (
+                    c.lang.clone(),
+                    c.instructionSet.clone(),
+                    c.compilerType.clone(),
+                    major_version(c),
+                )
+            };
+            let mut newest: HashMap<_, usize> = HashMap::new();
+            for (i, c) in selected.iter().enumerate() {
+                newest.insert(family(c), i);
             }
-            _ => after_version_min_filtered,
+            let keep = newest.into_values().collect::<HashSet<usize>>();
+            selected = selected
+                .into_iter()
+                .enumerate()
+                .filter(|(i, _)| keep.contains(i))
+                .map(|(_, c)| c)
+                .collect();
+        }
+        if let Some(n) = self.latest {
+            selected.drain(..selected.len().saturating_sub(n));
+        }
+        selected
+    }
+
+    fn matches(&self, compiler: &CompilerInfo) -> bool {
+        let in_category = || {
+            compiler
+                .compilerCategories
+                .as_ref()
+                .is_some_and(|categories| {
+                    categories
+                        .iter()
+                        .any(|c| self.categories.iter().any(|re| re.is_match(c)))
+                })
         };
 
-        return Some(after_version_max_filtered);
+        self.supports
+            .iter()
+            .all(|feature| compiler.supports(feature) == Some(true))
+            && (self.categories.is_empty() || in_category())
+            && self
+                .group
+                .as_ref()
+                .is_none_or(|re| compiler.group.as_ref().is_some_and(|g| re.is_match(g)))
+            && self
+                .nightly
+                .is_none_or(|nightly| compiler.isNightly.unwrap_or(false) == nightly)
+            && (self.include_hidden || !compiler.hidden.unwrap_or(false))
+            && self
+                .exclude_name
+                .as_ref()
+                .is_none_or(|re| !re.is_match(&compiler.name))
+            && !self.exclude_ids.contains(&compiler.id)
     }
-    None
 }
 
-async fn do_list_languages(session: &Session, _matches: &ArgMatches) {
-    if let Ok(mut all_languages) = languages(session).await {
-        all_languages.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-
-        for lang in all_languages {
-            println!("- {} (id: {})", lang.name, lang.id);
-        }
+/// First number of the compiler version, if it has one.
+fn major_version(compiler: &CompilerInfo) -> Option<i32> {
+    match Version::from(&compiler.semver)?.part(0) {
+        Ok(Part::Number(major)) => Some(major),
+        _ => None,
     }
 }
 
-async fn do_list_compilers(session: &Session, matches: &ArgMatches) {
+async fn do_list_compilers(client: &Client, matches: &ArgMatches) -> Result<(), Error> {
     let name = matches.get_one::<String>("name");
     let lang = matches.get_one::<String>("language");
     let isa = matches.get_one::<String>("isa");
     let version_min = matches.get_one::<String>("version-min");
     let version_max = matches.get_one::<String>("version-max");
+    let selector = CompilerSelector::from_matches(matches)?;
 
-    let maybe_compilers = find_compilers(
-        session,
-        false,
+    // Capabilities and the hidden flag are only part of the full listing.
+    let compilers = find_compilers(
+        client,
+        true,
         name.cloned(),
         lang.cloned(),
         isa.cloned(),
         version_min.cloned(),
         version_max.cloned(),
     )
-    .await;
-    if let Some(compilers) = maybe_compilers {
-        for c in compilers {
-            println!("- {}", c.to_text());
+    .await?;
+    let compilers = selector.select(compilers);
+    if compilers.is_empty() {
+        println!("No compiler found");
+    }
+    for c in compilers {
+        println!("- {}", c.to_text());
+    }
+    Ok(())
+}
+
+async fn do_show_compiler(client: &Client, matches: &ArgMatches) -> Result<(), Error> {
+    let compiler_info = get_compiler_info(client, matches.get_one::<String>("id").unwrap()).await?;
+
+    if matches.get_flag("json") {
+        let mut value = serde_json::to_value(&compiler_info).unwrap();
+        if let serde_json::Value::Object(fields) = &mut value {
+            fields.retain(|_, v| implems::is_populated(v));
         }
+        println!("{}", serde_json::to_string_pretty(&value).unwrap());
     } else {
-        println!("No compiler found");
+        println!("{}", compiler_info.to_details());
     }
+    Ok(())
 }
 
-async fn do_compile(session: &Session, matches: &ArgMatches) {
-    let is_summary = matches.get_one::<bool>("summary").unwrap();
+async fn do_show_language(client: &Client, matches: &ArgMatches) -> Result<(), Error> {
+    let language = get_language(client, matches.get_one::<String>("id").unwrap()).await?;
 
-    let mut filters_config = Filters::new()
-        .binary(*matches.get_one("binary").unwrap())
-        .binary_object(*matches.get_one("binary-object").unwrap())
-        .execute(*matches.get_one("execute").unwrap());
+    if matches.get_flag("json") {
+        println!("{}", serde_json::to_string_pretty(&language).unwrap());
+    } else {
+        println!("{}", language.to_details());
+    }
+    Ok(())
+}
 
-    let mut stdout_f = match matches.get_one::<String>("stdout") {
-        Some(s) if s == "-" => Some(Box::new(std::io::stdout()) as Box<dyn std::io::Write>),
-        Some(filename) => std::fs::File::create(filename)
-            .map(|f| Some(Box::new(f) as Box<dyn std::io::Write>))
-            .unwrap(),
-        _ => None,
+async fn do_list_libraries(client: &Client, matches: &ArgMatches) -> Result<(), Error> {
+    let lang = matches.get_one::<String>("language").unwrap();
+
+    let mut libraries = client.libraries(lang).await?;
+    libraries.sort_by_key(|l| l.id.to_lowercase());
+
+    for lib in libraries {
+        println!("- {}", lib.to_text());
+    }
+    Ok(())
+}
+
+/// Resolves the `--lib <id>@<version>` arguments against the libraries
+/// available for `language`.
+async fn libraries_from_matches(
+    client: &Client,
+    matches: &ArgMatches,
+    language: &str,
+) -> Result<Vec<Library>, Error> {
+    let Some(specs) = matches.get_many::<String>("libs") else {
+        return Ok(Vec::new());
     };
 
-    let mut stderr_f = match matches.get_one::<String>("stderr") {
-        Some(s) if s == "-" => Some(Box::new(std::io::stderr()) as Box<dyn std::io::Write>),
-        Some(filename) => std::fs::File::create(filename)
-            .map(|f| Some(Box::new(f) as Box<dyn std::io::Write>))
-            .unwrap(),
-        _ => None,
+    let catalog = client.libraries(language).await?;
+    let mut libraries = Vec::new();
+
+    for spec in specs {
+        let (id, version) = spec
+            .split_once('@')
+            .ok_or_else(|| Error::InvalidLibrary { spec: spec.clone() })?;
+
+        let info = catalog
+            .iter()
+            .find(|l| l.id == id)
+            .ok_or_else(|| Error::UnknownLibrary {
+                id: id.to_string(),
+                language: language.to_string(),
+            })?;
+
+        let found = info
+            .find_version(version)
+            .ok_or_else(|| Error::UnknownLibraryVersion {
+                id: id.to_string(),
+                version: version.to_string(),
+                available: info
+                    .versions
+                    .iter()
+                    .map(|v| v.to_text())
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            })?;
+
+        libraries.push(Library {
+            id: info.id.clone(),
+            version: found.id.clone(),
+        });
+    }
+    Ok(libraries)
+}
+
+async fn do_list_tools(client: &Client, matches: &ArgMatches) -> Result<(), Error> {
+    let lang = matches.get_one::<String>("language").unwrap();
+
+    let mut tools = client.tools(lang).await?;
+    tools.sort_by_key(|t| t.id.to_lowercase());
+
+    for tool in tools {
+        println!("- {}", tool.to_text());
+    }
+    Ok(())
+}
+
+/// Resolves the `--tool <id>[:<args>]` arguments against the tools available
+/// for `language`.
+async fn tools_from_matches(
+    client: &Client,
+    matches: &ArgMatches,
+    language: &str,
+) -> Result<Vec<Tool>, Error> {
+    let Some(specs) = matches.get_many::<String>("tools") else {
+        return Ok(Vec::new());
     };
 
+    let catalog = client.tools(language).await?;
+    let mut tools = Vec::new();
+
+    for spec in specs {
+        let (id, args) = spec.split_once(':').unwrap_or((spec, ""));
+
+        if !catalog.iter().any(|t| t.id == id) {
+            return Err(Error::UnknownTool {
+                id: id.to_string(),
+                language: language.to_string(),
+            });
+        }
+        tools.push(Tool {
+            id: id.to_string(),
+            args: args.to_string(),
+        });
+    }
+    Ok(tools)
+}
+
+fn compare_semver(a: &CompilerInfo, b: &CompilerInfo) -> Ordering {
+    let va = Version::from(&a.semver);
+    let vb = Version::from(&b.semver);
+
+    let by_version = match (va, vb) {
+        (Some(va), Some(vb)) => va.partial_cmp(&vb).unwrap_or(Ordering::Equal),
+        // Compilers without a parsable version (trunk, nightlies, ...) go last.
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    };
+    by_version.then_with(|| a.name.cmp(&b.name))
+}
+
+fn filters_from_matches(matches: &ArgMatches) -> Filters {
+    let mut filters_config = Filters::new()
+        .binary(*matches.get_one("binary").unwrap())
+        .binary_object(*matches.get_one("binary-object").unwrap())
+        .execute(*matches.get_one("execute").unwrap());
+
     if let Some(filters) = matches.get_many::<String>("filters") {
-        //let enabled_filters: Vec<_> = matches.get_many::<String> ("filters").unwrap().collect();
         filters_config = Filters::all_disabled();
-        let enabled_filters: Vec<_> = filters.collect();
-        for f in enabled_filters {
-            match f.as_str() {
-                "binary" => filters_config.binary = true,
-                "binaryObject" => filters_config.binaryObject = true,
-                "commentOnly" => filters_config.commentOnly = true,
-                "demangle" => filters_config.demangle = true,
-                "directives" => filters_config.directives = true,
-                "execute" => filters_config.execute = true,
-                "intel" => filters_config.intel = true,
-                "labels" => filters_config.labels = true,
-                "libraryCode" => filters_config.libraryCode = true,
-                "trim" => filters_config.trim = true,
-                "debugCalls" => filters_config.debugCalls = true,
-                &_ => println!("Unknown filter: {}", f),
-            }
+        for f in filters {
+            // Names are checked by clap against `Filters::NAMES`.
+            filters_config.set(f, true);
         }
     }
+    filters_config
+}
 
-    let source_data = if let Some(source_text) = matches.get_one::<String>("source") {
-        source_text.clone()
-    } else if let Some(source_file) = matches.get_one::<String>("source-file") {
-        std::fs::read_to_string(source_file).expect("Unable to read file")
+/// Filters asked for on the command line, as opposed to the ones enabled by
+/// default.
+fn requested_filters(matches: &ArgMatches) -> Vec<&'static str> {
+    match matches.get_many::<String>("filters") {
+        Some(filters) => {
+            let filters = filters.collect::<Vec<&String>>();
+            Filters::NAMES
+                .into_iter()
+                .filter(|name| filters.iter().any(|f| f == name))
+                .collect()
+        }
+        None => [
+            ("binary", "binary"),
+            ("binary-object", "binaryObject"),
+            ("execute", "execute"),
+        ]
+        .into_iter()
+        .filter_map(|(id, name)| matches.get_one::<bool>(id).unwrap().then_some(name))
+        .collect(),
+    }
+}
+
+/// Enabled filters that aren't enabled by default.
+fn non_default_filters(filters: &Filters) -> Vec<&'static str> {
+    let defaults = Filters::default().enabled();
+    filters
+        .enabled()
+        .into_iter()
+        .filter(|f| !defaults.contains(f))
+        .collect()
+}
+
+/// File holding the source, given with `--source-file` or as positional
+/// argument. `-` is stdin.
+fn source_file_from_matches(matches: &ArgMatches) -> Option<&String> {
+    matches
+        .get_one::<String>("source-file")
+        .or(matches.get_one::<String>("file"))
+}
+
+fn source_from_matches(matches: &ArgMatches) -> Result<String, Error> {
+    if let Some(source_text) = matches.get_one::<String>("source") {
+        Ok(source_text.clone())
+    } else if let Some(source_file) = source_file_from_matches(matches) {
+        if source_file == "-" {
+            let exec_stdin = matches.try_get_one::<String>("exec-stdin").ok().flatten();
+            if exec_stdin.is_some_and(|s| s == "-") {
+                return Err(Error::StdinConflict);
+            }
+            std::io::read_to_string(std::io::stdin()).map_err(|source| Error::Io {
+                path: "<stdin>".to_string(),
+                source,
+            })
+        } else {
+            std::fs::read_to_string(source_file).map_err(|source| Error::Io {
+                path: source_file.clone(),
+                source,
+            })
+        }
     } else {
-        "no".to_string() // FIXME
+        Err(Error::NoSource)
+    }
+}
+
+/// An output destination, with the name used in error messages.
+type NamedOutput = Option<(String, Box<dyn std::io::Write>)>;
+
+/// Opens the destination given to `id`: `-` is `console`, anything else a file.
+fn output_from_matches(
+    matches: &ArgMatches,
+    id: &str,
+    console: fn() -> Box<dyn std::io::Write>,
+) -> Result<NamedOutput, Error> {
+    match matches.get_one::<String>(id) {
+        Some(s) if s == "-" => Ok(Some((s.clone(), console()))),
+        Some(filename) => std::fs::File::create(filename)
+            .map(|f| Some((filename.clone(), Box::new(f) as Box<dyn std::io::Write>)))
+            .map_err(|source| Error::Io {
+                path: filename.clone(),
+                source,
+            }),
+        _ => Ok(None),
+    }
+}
+
+fn write_output(output: &mut NamedOutput, text: &str) -> Result<(), Error> {
+    if let Some((name, f)) = output {
+        f.write_all(text.as_bytes()).map_err(|source| Error::Io {
+            path: name.clone(),
+            source,
+        })?;
+    }
+    Ok(())
+}
+
+/// Program arguments and standard input given with `--exec-arg` and
+/// `--exec-stdin`.
+fn execute_parameters_from_matches(matches: &ArgMatches) -> Result<(Vec<String>, String), Error> {
+    let args = matches
+        .get_many::<String>("exec-args")
+        .map(|args| args.cloned().collect())
+        .unwrap_or_default();
+
+    let stdin = match matches.get_one::<String>("exec-stdin") {
+        Some(s) if s == "-" => {
+            std::io::read_to_string(std::io::stdin()).map_err(|source| Error::Io {
+                path: "<stdin>".to_string(),
+                source,
+            })?
+        }
+        Some(filename) => std::fs::read_to_string(filename).map_err(|source| Error::Io {
+            path: filename.clone(),
+            source,
+        })?,
+        _ => String::new(),
     };
+    Ok((args, stdin))
+}
 
-    let flags = if let Some(f) = matches.get_one::<String>("flags") {
+fn flags_from_matches(matches: &ArgMatches) -> String {
+    if let Some(f) = matches.get_one::<String>("flags") {
         f.clone()
     } else {
         "".to_string()
-    };
+    }
+}
 
-    let compilers_id = if let Some(id) = matches.get_one::<String>("compiler-id") {
-        vec![get_compiler_info(session, id).await.unwrap()]
+/// Compilers selected by `--id` or the other selection arguments.
+/// `default_lang` is a language id used when `--language` isn't given.
+async fn selected_compilers(
+    client: &Client,
+    matches: &ArgMatches,
+    default_lang: Option<&str>,
+) -> Result<Vec<CompilerInfo>, Error> {
+    let compilers = if let Some(id) = matches.try_get_one::<String>("compiler-id").ok().flatten() {
+        vec![get_compiler_info(client, id).await?]
     } else {
         let name = matches.get_one::<String>("compiler-name");
-        let lang = matches.get_one::<String>("compiler-lang");
+        let lang = matches
+            .get_one::<String>("compiler-lang")
+            .cloned()
+            .or(default_lang.map(regex::escape));
         let isa = matches.get_one::<String>("compiler-isa");
         let version_min = matches.get_one::<String>("version-min");
         let version_max = matches.get_one::<String>("version-max");
+        let selector = CompilerSelector::from_matches(matches)?;
 
-        find_compilers(
-            session,
+        let compilers = find_compilers(
+            client,
             true,
             name.cloned(),
-            lang.cloned(),
+            lang,
             isa.cloned(),
             version_min.cloned(),
             version_max.cloned(),
         )
-        .await
-        .unwrap()
+        .await?;
+        selector.select(compilers)
+    };
+
+    if compilers.is_empty() {
+        return Err(Error::NoCompiler);
+    }
+    Ok(compilers)
+}
+
+/// How the files of `--project` are compiled.
+#[derive(Clone)]
+enum ProjectMode {
+    /// With the CMake endpoint, the job source being the `CMakeLists.txt`.
+    CMake,
+    /// With the compile endpoint, the job source being this main file.
+    MainFile(String),
+}
+
+impl ProjectMode {
+    /// Name of the file sent as the job source.
+    fn main_file(&self) -> &str {
+        match self {
+            ProjectMode::CMake => "CMakeLists.txt",
+            ProjectMode::MainFile(name) => name,
+        }
+    }
+}
+
+/// Adds the files below `dir` to `files`, named by their path relative to
+/// `root`. Hidden files and directories are skipped, as are symlinks to
+/// directories (which could loop) and files that aren't UTF-8 text (objects,
+/// images, ...).
+fn read_project_dir(root: &Path, dir: &Path, files: &mut Vec<FiledataPair>) -> Result<(), Error> {
+    let io_error = |path: &Path, source| Error::Io {
+        path: path.display().to_string(),
+        source,
+    };
+    let mut entries = std::fs::read_dir(dir)
+        .and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+        .map_err(|source| io_error(dir, source))?;
+    entries.sort_by_key(|e| e.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let file_type = entry
+            .file_type()
+            .map_err(|source| io_error(&path, source))?;
+        if file_type.is_dir() {
+            read_project_dir(root, &path, files)?;
+            continue;
+        }
+        if file_type.is_symlink() && path.is_dir() {
+            eprintln!(
+                "Warning: {} is a symlink to a directory, skipped",
+                path.display()
+            );
+            continue;
+        }
+
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::InvalidData => {
+                eprintln!("Warning: {} isn't UTF-8 text, skipped", path.display());
+                continue;
+            }
+            Err(source) => return Err(io_error(&path, source)),
+        };
+        let filename = path
+            .strip_prefix(root)
+            .unwrap()
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        files.push(FiledataPair { filename, contents });
+    }
+    Ok(())
+}
+
+/// Reads the `--project` directory: the job source, the other files and how
+/// to compile them. The directory is built with CMake when it has a
+/// `CMakeLists.txt` and no `--main` is given.
+fn project_from_matches(
+    matches: &ArgMatches,
+) -> Result<Option<(String, Vec<FiledataPair>, ProjectMode)>, Error> {
+    let Some(dir) = matches.get_one::<String>("project") else {
+        return Ok(None);
+    };
+    let mut files = Vec::new();
+    read_project_dir(Path::new(dir), Path::new(dir), &mut files)?;
+
+    let main_file = match matches.get_one::<String>("main") {
+        Some(main) => main.trim_start_matches("./").to_string(),
+        None if files.iter().any(|f| f.filename == "CMakeLists.txt") => {
+            "CMakeLists.txt".to_string()
+        }
+        None => return Err(Error::ProjectMain { dir: dir.clone() }),
+    };
+    let Some(pos) = files.iter().position(|f| f.filename == main_file) else {
+        return Err(Error::Io {
+            path: Path::new(dir).join(&main_file).display().to_string(),
+            source: std::io::ErrorKind::NotFound.into(),
+        });
+    };
+    let source = files.remove(pos).contents;
+
+    let mode = if matches.contains_id("main") {
+        ProjectMode::MainFile(main_file)
+    } else {
+        ProjectMode::CMake
+    };
+    Ok(Some((source, files, mode)))
+}
+
+/// Language of a source file with `extension` (dot included), `None` when no
+/// language lists it.
+///
+/// Languages list their primary extension first: `.c` is also listed by C++,
+/// but only C has it first. Other positions don't say much, `.h` is secondary
+/// for both C and C++. Several languages can also share a primary extension
+/// (C++, Cppx and Circle all start with `.cpp`), then the one named after the
+/// extension by its id or an alias (`cpp` is an alias of C++) wins.
+fn language_for_extension<'a>(
+    languages: &'a [Language],
+    extension: &str,
+) -> Result<Option<&'a Language>, Error> {
+    let stem = extension.trim_start_matches('.');
+    let rank = |l: &Language| {
+        let position = l.extensions.iter().position(|e| e == extension)?;
+        let named = l.id == stem || l.alias.iter().flatten().any(|a| a == stem);
+        Some((position.min(1), !named))
+    };
+
+    let candidates = languages
+        .iter()
+        .filter_map(|l| Some((rank(l)?, l)))
+        .collect::<Vec<_>>();
+    let Some(best) = candidates.iter().map(|(rank, _)| *rank).min() else {
+        return Ok(None);
+    };
+    let best_candidates = candidates
+        .into_iter()
+        .filter(|(rank, _)| *rank == best)
+        .map(|(_, l)| l)
+        .collect::<Vec<&Language>>();
+    match best_candidates.as_slice() {
+        [language] => Ok(Some(language)),
+        _ => Err(Error::AmbiguousLanguage {
+            extension: extension.to_string(),
+            candidates: best_candidates
+                .iter()
+                .map(|l| l.id.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        }),
+    }
+}
+
+/// Language of a CMake project: the one of most of its files, C++ when none
+/// of them has a known (and unambiguous) extension.
+fn project_language(languages: &[Language], files: &[FiledataPair]) -> String {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for file in files {
+        let Some(extension) = Path::new(&file.filename).extension() else {
+            continue;
+        };
+        let extension = format!(".{}", extension.to_string_lossy());
+        if let Ok(Some(language)) = language_for_extension(languages, &extension) {
+            *counts.entry(&language.id).or_default() += 1;
+        }
+    }
+    counts
+        .into_iter()
+        .max_by(|(id_a, a), (id_b, b)| a.cmp(b).then(id_b.cmp(id_a)))
+        .map_or("c++".to_string(), |(id, _)| id.to_string())
+}
+
+/// Language of `--source-file` (or `--main`) found from its extension, or of
+/// the files of a CMake project, when neither `--language` nor `--id` is
+/// given.
+async fn language_from_source_file(
+    client: &Client,
+    matches: &ArgMatches,
+    project: Option<&ProjectMode>,
+    files: &[FiledataPair],
+) -> Result<Option<String>, Error> {
+    if matches.contains_id("compiler-lang") || matches.contains_id("compiler-id") {
+        return Ok(None);
+    }
+    if let Some(ProjectMode::CMake) = project {
+        return Ok(Some(project_language(&client.languages().await?, files)));
+    }
+    let Some(extension) = source_file_from_matches(matches)
+        .or(matches.try_get_one::<String>("main").ok().flatten())
+        .and_then(|f| Path::new(f).extension())
+        .and_then(|e| e.to_str())
+    else {
+        return Ok(None);
+    };
+    let languages = client.languages().await?;
+    let language = language_for_extension(&languages, &format!(".{}", extension))?;
+    Ok(language.map(|l| l.id.clone()))
+}
+
+/// When the language (given with `--language`, or `default_lang` otherwise,
+/// usually inferred from the source file extension) is the only compiler
+/// selection, the default compiler of each matching language instead of all
+/// their compilers, unless `--all-compilers` is given. `None` when the usual
+/// selection applies, including when a matching language has no default
+/// compiler.
+async fn default_compilers(
+    client: &Client,
+    matches: &ArgMatches,
+    default_lang: Option<&str>,
+) -> Result<Option<Vec<CompilerInfo>>, Error> {
+    let Some(lang) = matches
+        .get_one::<String>("compiler-lang")
+        .cloned()
+        .or(default_lang.map(regex::escape))
+    else {
+        return Ok(None);
+    };
+    let other_selection = [
+        "compiler-id",
+        "compiler-name",
+        "compiler-isa",
+        "version-min",
+        "version-max",
+    ]
+    .iter()
+    .any(|id| matches.contains_id(id));
+    if other_selection
+        || matches.get_flag("all-compilers")
+        || CompilerSelector::from_matches(matches)?.is_set()
+    {
+        return Ok(None);
+    }
+
+    // Same exact match as find_compilers()
+    let re = new_regex(&format!(r"(?i)^{}$", lang))?;
+    let mut compilers = Vec::new();
+    for language in client.languages().await? {
+        if !re.is_match(&language.id) {
+            continue;
+        }
+        match &language.defaultCompiler {
+            Some(id) if !id.is_empty() => compilers.push(get_compiler_info(client, id).await?),
+            _ => return Ok(None),
+        }
+    }
+
+    if compilers.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(compilers))
+}
+
+/// One compilation to run.
+struct CompileTask {
+    compiler_info: CompilerInfo,
+    job: CompileJob,
+    /// Requested filters the compiler doesn't support, disabled in `job`.
+    dropped_filters: Vec<&'static str>,
+    /// Set when compiling a `--project`.
+    project: Option<ProjectMode>,
+}
+
+impl CompileTask {
+    /// Disables the filters of `job` the compiler doesn't support. Only the
+    /// `requested` ones are reported, the others are enabled by default and
+    /// silently ignored by Compiler-Explorer anyway.
+    fn new(compiler_info: CompilerInfo, mut job: CompileJob, requested: &[&str]) -> Self {
+        let mut dropped_filters = Vec::new();
+        for name in job.options.filters.enabled() {
+            if !compiler_info.supports_filter(name) {
+                job.options.filters.set(name, false);
+                if requested.contains(&name) {
+                    dropped_filters.push(name);
+                }
+            }
+        }
+        CompileTask {
+            compiler_info,
+            job,
+            dropped_filters,
+            project: None,
+        }
+    }
+
+    fn execute_dropped(&self) -> bool {
+        self.dropped_filters.contains(&"execute")
+    }
+}
+
+/// Reports the compilers that don't support some of the requested filters.
+/// With `--strict-filters`, these compilers are skipped or make the whole
+/// command fail.
+fn check_filters(matches: &ArgMatches, tasks: Vec<CompileTask>) -> Result<Vec<CompileTask>, Error> {
+    let strict = matches
+        .get_one::<String>("strict-filters")
+        .map(String::as_str);
+    let mut checked = Vec::new();
+
+    for task in tasks {
+        if task.dropped_filters.is_empty() {
+            checked.push(task);
+            continue;
+        }
+        let filters = task.dropped_filters.join(", ");
+        match strict {
+            Some("fail") => {
+                return Err(Error::UnsupportedFilters {
+                    compiler: task.compiler_info.name,
+                    filters,
+                })
+            }
+            Some(_) => eprintln!(
+                "Skipping \"{}\", unsupported filter(s): {}",
+                task.compiler_info.name, filters
+            ),
+            None => {
+                eprintln!(
+                    "Warning: \"{}\" doesn't support filter(s) {}, they are disabled",
+                    task.compiler_info.name, filters
+                );
+                checked.push(task);
+            }
+        }
+    }
+
+    if checked.is_empty() {
+        return Err(Error::NoCompiler);
+    }
+    Ok(checked)
+}
+
+async fn do_compile(client: &Client, matches: &ArgMatches) -> Result<(), Error> {
+    let mut filters_config = filters_from_matches(matches);
+    let mut requested = requested_filters(matches);
+    let expectations = ExecExpectations::from_matches(matches)?;
+    if !expectations.is_empty() {
+        filters_config.execute = true;
+        if !requested.contains(&"execute") {
+            requested.push("execute");
+        }
+    }
+
+    let (source_data, mut files, project) = match project_from_matches(matches)? {
+        Some((source, files, mode)) => (source, files, Some(mode)),
+        None => (source_from_matches(matches)?, Vec::new(), None),
+    };
+    if matches.get_flag("bundle-includes") {
+        let source_dir = source_file_from_matches(matches)
+            .filter(|f| *f != "-")
+            .and_then(|f| Path::new(f).parent())
+            .unwrap_or(Path::new(""));
+        let include_dirs = matches
+            .get_many::<String>("include-dirs")
+            .map(|dirs| dirs.map(PathBuf::from).collect::<Vec<PathBuf>>())
+            .unwrap_or_default();
+        let (headers, unresolved) =
+            includes::bundle_includes(&source_data, source_dir, &include_dirs)?;
+        for name in unresolved {
+            eprintln!("Warning: #include \"{}\" can't be bundled", name);
+        }
+        files = headers;
+    }
+    let flags = flags_from_matches(matches);
+    let (exec_args, exec_stdin) = execute_parameters_from_matches(matches)?;
+    let source_lang = language_from_source_file(client, matches, project.as_ref(), &files).await?;
+    let mut compilers_id = match default_compilers(client, matches, source_lang.as_deref()).await? {
+        Some(compilers) => compilers,
+        None => selected_compilers(client, matches, source_lang.as_deref()).await?,
+    };
+    compilers_id.sort_by(compare_semver);
+
+    // Libraries and tools depend on the language, resolve them once per language.
+    let mut libraries: HashMap<String, Vec<Library>> = HashMap::new();
+    let mut tools: HashMap<String, Vec<Tool>> = HashMap::new();
+    for compiler_info in &compilers_id {
+        if !libraries.contains_key(&compiler_info.lang) {
+            let libs = libraries_from_matches(client, matches, &compiler_info.lang).await?;
+            libraries.insert(compiler_info.lang.clone(), libs);
+            let lang_tools = tools_from_matches(client, matches, &compiler_info.lang).await?;
+            tools.insert(compiler_info.lang.clone(), lang_tools);
+        }
+    }
+
+    let tasks = compilers_id
         .into_iter()
-        .collect::<Vec<CompilerInfo>>()
+        .map(|compiler_info| {
+            let mut job = CompileJob::build(&source_data, &flags, &filters_config)
+                .execute_parameters(exec_args.clone(), &exec_stdin)
+                .libraries(libraries[&compiler_info.lang].clone())
+                .tools(tools[&compiler_info.lang].clone())
+                .files(files.clone());
+            // CMake jobs always need a language, use the compiler's one when
+            // none was inferred.
+            match (&source_lang, &project) {
+                (Some(lang), _) => job = job.lang(lang),
+                (None, Some(ProjectMode::CMake)) => job = job.lang(&compiler_info.lang),
+                _ => (),
+            }
+            let mut task = CompileTask::new(compiler_info, job, &requested);
+            task.project = project.clone();
+            task
+        })
+        .collect::<Vec<CompileTask>>();
+    let tasks = check_filters(matches, tasks)?;
+
+    if *matches.get_one::<bool>("share").unwrap() {
+        let url = share_tasks(client, &tasks).await?;
+        if matches.get_one::<String>("output-format").unwrap() == "text" {
+            println!("Shortlink: {}", url);
+        } else {
+            eprintln!("Shortlink: {}", url);
+        }
+    }
+
+    run_compile_tasks(client, matches, tasks, &expectations).await
+}
+
+/// Creates a shortlink with one session per language, holding every compiler
+/// of `tasks` configured like its job. Sessions have a single source, bundled
+/// headers are inlined in it.
+async fn share_tasks(client: &Client, tasks: &[CompileTask]) -> Result<String, Error> {
+    let mut sessions: Vec<Session> = Vec::new();
+
+    for task in tasks {
+        let lang = &task.compiler_info.lang;
+        let session = match sessions.iter().position(|s| &s.language == lang) {
+            Some(i) => &mut sessions[i],
+            None => {
+                sessions.push(Session {
+                    id: sessions.len() + 1,
+                    language: lang.clone(),
+                    source: includes::inline_includes(&task.job.source, &task.job.files),
+                    conformanceview: false,
+                    compilers: Vec::new(),
+                    executors: Vec::new(),
+                });
+                sessions.last_mut().unwrap()
+            }
+        };
+
+        let options = &task.job.options;
+        session.compilers.push(CompilerConfig {
+            _internalid: session.compilers.len() + 1,
+            id: task.compiler_info.id.clone(),
+            options: options.userArguments.clone(),
+            filters: options.filters.clone(),
+            libs: options.libraries.clone(),
+            specialoutputs: Vec::new(),
+            tools: options.tools.clone(),
+        });
+    }
+
+    let state = ShortLinkInfo {
+        sessions,
+        trees: Vec::new(),
     };
+    Ok(client.shorten(&state).await?)
+}
+
+/// Runs `tasks` and prints their results as requested by the output
+/// arguments (see `output_args()`) in `matches`.
+async fn run_compile_tasks(
+    client: &Client,
+    matches: &ArgMatches,
+    tasks: Vec<CompileTask>,
+    expectations: &ExecExpectations,
+) -> Result<(), Error> {
+    let is_summary = matches.get_one::<bool>("summary").unwrap();
+    let is_interleaved = *matches.get_one::<bool>("interleave").unwrap();
+    let output_format = matches.get_one::<String>("output-format").unwrap();
+    let jobs = *matches.get_one::<u8>("jobs").unwrap() as usize;
 
-    for compiler_info in compilers_id {
-        // println!("{:?}", compiler_info);
-        let compiler_id = compiler_info.id;
-        let mut local_filters = filters_config.clone();
-        if !compiler_info.supportsExecute.unwrap() {
-            local_filters = local_filters.execute(false)
-        }
-        let simple_job = CompileJob::build(&source_data, &flags, &local_filters);
+    let mut stdout_f = output_from_matches(matches, "stdout", || Box::new(std::io::stdout()))?;
+    let mut stderr_f = output_from_matches(matches, "stderr", || Box::new(std::io::stderr()))?;
+    let mut exec_stdout_f =
+        output_from_matches(matches, "exec-stdout", || Box::new(std::io::stdout()))?;
+    let mut exec_stderr_f =
+        output_from_matches(matches, "exec-stderr", || Box::new(std::io::stderr()))?;
+    // Outputs sent to the console with `-` are not printed a second time.
+    let to_console = |id: &str| matches.get_one::<String>(id).is_some_and(|s| s == "-");
+    let exec_stdout_shown = to_console("exec-stdout");
+    let exec_stderr_shown = to_console("exec-stderr");
+
+    let fail_on = matches.get_one::<String>("fail-on").unwrap();
+    let expect_fail = *matches.get_one::<bool>("expect-fail").unwrap();
+    let mut total = 0;
+    let mut unexpected = 0;
+
+    let mut json_results = Vec::new();
+
+    // Requests run concurrently, but `buffered` yields them in submission
+    // order so the output stays sorted by version.
+    let mut results = stream::iter(tasks.into_iter().map(|task| async move {
+        let compile_ret1 = match task.project {
+            Some(ProjectMode::CMake) => {
+                client.cmake(&task.compiler_info.id, task.job.clone()).await
+            }
+            _ => {
+                client
+                    .compile(&task.compiler_info.id, task.job.clone())
+                    .await
+            }
+        };
+        (task, compile_ret1)
+    }))
+    .buffered(jobs);
 
-        let compile_ret1 = compile(session, &compiler_id, simple_job.clone()).await;
+    while let Some((task, compile_ret1)) = results.next().await {
+        let execute_dropped = task.execute_dropped();
+        let compiler_info = task.compiler_info;
+        let ret1 = compile_ret1?;
 
-        let ret1 = compile_ret1.unwrap();
+        let failed_expectations = if expectations.is_empty() {
+            Vec::new()
+        } else {
+            expectations.check(ret1.execResult.as_ref())
+        };
+        let succeeded = if expectations.is_empty() {
+            ret1.succeeded()
+        } else {
+            ret1.code == 0 && failed_expectations.is_empty()
+        };
 
-        if let Some(ref mut f) = &mut stdout_f {
-            f.write_all(ret1.stdout.to_text().as_bytes()).unwrap();
+        total += 1;
+        if succeeded == expect_fail {
+            unexpected += 1;
+        }
+
+        match &task.project {
+            Some(mode) => {
+                write_output(
+                    &mut stdout_f,
+                    &ret1.stdout.to_located_text(mode.main_file()),
+                )?;
+                write_output(
+                    &mut stderr_f,
+                    &ret1.stderr.to_located_text(mode.main_file()),
+                )?;
+            }
+            None => {
+                write_output(&mut stdout_f, &ret1.stdout.to_text())?;
+                write_output(&mut stderr_f, &ret1.stderr.to_text())?;
+            }
+        }
+        if let Some(exec_result) = &ret1.execResult {
+            write_output(&mut exec_stdout_f, &exec_result.stdout.to_text())?;
+            write_output(&mut exec_stderr_f, &exec_result.stderr.to_text())?;
         }
 
-        if let Some(ref mut f) = &mut stderr_f {
-            f.write_all(ret1.stderr.to_text().as_bytes()).unwrap();
+        match output_format.as_str() {
+            "json" => {
+                json_results.push((
+                    compiler_info,
+                    ret1,
+                    task.dropped_filters,
+                    failed_expectations,
+                ));
+                continue;
+            }
+            "jsonl" => {
+                let report = CompileReport::new(
+                    &compiler_info,
+                    &ret1,
+                    &task.dropped_filters,
+                    &failed_expectations,
+                );
+                println!("{}", serde_json::to_string(&report).unwrap());
+                continue;
+            }
+            _ => (),
         }
 
-        if !is_summary {
-            println!("{}", ret1.asm.to_text());
+        if is_interleaved {
+            let items = ret1.asm.items();
+            let interleaved = match &task.project {
+                None => interleave::interleave(&task.job.source, None, items),
+                Some(ProjectMode::CMake) => {
+                    interleave::interleave_files(None, &task.job.files, items)
+                }
+                Some(ProjectMode::MainFile(name)) => interleave::interleave_files(
+                    Some((name, &task.job.source)),
+                    &task.job.files,
+                    items,
+                ),
+            };
+            println!("{}", interleaved);
+        } else if !is_summary {
+            let highlighter = Highlighter::new(
+                &compiler_info.instructionSet,
+                task.job.options.filters.intel,
+            );
+            for item in ret1.asm.items() {
+                println!("{}", highlighter.highlight(item));
+            }
         } else {
             println!(
                 "{} Compilation \"{}\" ({})",
@@ -370,7 +1397,7 @@ async fn do_compile(session: &Session, matches: &ArgMatches) {
                 ret1.code,
             );
         }
-        if filters_config.execute != local_filters.execute {
+        if execute_dropped {
             if !is_summary {
                 println!("Execution not supported\n");
             } else {
@@ -381,30 +1408,599 @@ async fn do_compile(session: &Session, matches: &ArgMatches) {
                 );
             }
         }
-        if let Some(exec_result) = ret1.execResult {
+        if let Some(exec_result) = &ret1.execResult {
+            let exec_ok = if expectations.is_empty() {
+                exec_result.code == 0
+            } else {
+                failed_expectations.is_empty()
+            };
             if !is_summary {
-                println!("Execution:\n{}", exec_result.stdout.to_text());
+                if !exec_stdout_shown {
+                    println!("Execution:\n{}", exec_result.stdout.to_text());
+                }
+                if !exec_stderr_shown && !exec_result.stderr.is_empty() {
+                    println!("Execution stderr:\n{}", exec_result.stderr.to_text());
+                }
             } else {
                 println!(
                     "{} Execution \"{}\" ({})",
-                    (if exec_result.code == 0 {
+                    (if exec_ok { "✔".green() } else { "✗".red() }),
+                    compiler_info.name,
+                    exec_result.code
+                );
+            }
+        }
+        for failure in &failed_expectations {
+            println!("{} {}", "Expectation failed:".red(), failure);
+        }
+        for tool in &ret1.tools {
+            if !is_summary {
+                println!("Tool {} ({}):", tool.name(), tool.code);
+                println!("{}", tool.stdout.to_text());
+                if !tool.stderr.is_empty() {
+                    println!("{}", tool.stderr.to_text());
+                }
+            } else {
+                println!(
+                    "{} Tool {} \"{}\" ({})",
+                    (if tool.code == 0 {
                         "✔".green()
                     } else {
                         "✗".red()
                     }),
+                    tool.name(),
                     compiler_info.name,
-                    exec_result.code
+                    tool.code
                 );
             }
         }
     }
+
+    if output_format == "json" {
+        let reports = json_results
+            .iter()
+            .map(
+                |(compiler_info, result, dropped_filters, failed_expectations)| {
+                    CompileReport::new(compiler_info, result, dropped_filters, failed_expectations)
+                },
+            )
+            .collect::<Vec<CompileReport>>();
+        println!("{}", serde_json::to_string_pretty(&reports).unwrap());
+    }
+
+    let failed = match fail_on.as_str() {
+        "any" => unexpected > 0,
+        "all" => unexpected == total,
+        _ => false,
+    };
+    if failed {
+        return Err(Error::Outcome { unexpected, total });
+    }
+    Ok(())
+}
+
+/// Extracts the shortlink id from either an id or a full URL
+/// (`https://godbolt.org/z/xxxxxxxx`).
+fn shortlink_id(link: &str) -> &str {
+    link.trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or(link)
+}
+
+async fn do_run_link(client: &Client, matches: &ArgMatches) -> Result<(), Error> {
+    let link = matches.get_one::<String>("link").unwrap();
+    let link_info = client.shortlinkinfo(shortlink_id(link)).await?;
+
+    let override_compilers = ["compiler-name", "compiler-lang", "compiler-isa"]
+        .iter()
+        .chain(["version-min", "version-max"].iter())
+        .any(|id| matches.contains_id(id))
+        || CompilerSelector::from_matches(matches)?.is_set();
+
+    let expectations = ExecExpectations::from_matches(matches)?;
+    let all_compilers = client.compilers(true).await?;
+    let mut tasks = Vec::new();
+
+    for session in link_info.sessions {
+        if !session.executors.is_empty() {
+            eprintln!("Warning: executors of session {} are ignored", session.id);
+        }
+
+        let new_task = |compiler_info: CompilerInfo, config: &CompilerConfig| {
+            let mut filters = config.filters.clone();
+            filters.execute |= !expectations.is_empty();
+            let job = CompileJob::build(&session.source, &config.options, &filters)
+                .lang(&session.language)
+                .libraries(config.libs.clone())
+                .tools(config.tools.clone());
+            CompileTask::new(compiler_info, job, &non_default_filters(&filters))
+        };
+
+        if override_compilers {
+            // Everything but the compiler comes from the first compiler of the session.
+            let Some(config) = session.compilers.first() else {
+                continue;
+            };
+            let mut compilers =
+                selected_compilers(client, matches, Some(&session.language)).await?;
+            compilers.sort_by(compare_semver);
+            for compiler_info in compilers {
+                tasks.push(new_task(compiler_info, config));
+            }
+        } else {
+            for config in &session.compilers {
+                let compiler_info = all_compilers
+                    .iter()
+                    .find(|c| c.id == config.id)
+                    .cloned()
+                    .ok_or_else(|| Error::UnknownCompiler {
+                        id: config.id.clone(),
+                    })?;
+                tasks.push(new_task(compiler_info, config));
+            }
+        }
+    }
+
+    if tasks.is_empty() {
+        return Err(Error::NoCompiler);
+    }
+    let tasks = check_filters(matches, tasks)?;
+    run_compile_tasks(client, matches, tasks, &expectations).await
+}
+
+/// What makes a compilation "bad" when bisecting. A result is bad as soon as
+/// one of the enabled predicates holds (or none of them, when inverted).
+struct BisectPredicate {
+    compile_fail: bool,
+    exec_fail: bool,
+    compile_exit_code: Option<i32>,
+    exec_exit_code: Option<i32>,
+    stdout_re: Option<Regex>,
+    stderr_re: Option<Regex>,
+    asm_re: Option<Regex>,
+    invert: bool,
+}
+
+impl BisectPredicate {
+    fn from_matches(matches: &ArgMatches) -> Result<Self, Error> {
+        let regex = |id: &str| {
+            matches
+                .get_one::<String>(id)
+                .map(|r| new_regex(r))
+                .transpose()
+        };
+
+        let mut predicate = BisectPredicate {
+            compile_fail: *matches.get_one::<bool>("compile-fail").unwrap(),
+            exec_fail: *matches.get_one::<bool>("exec-fail").unwrap(),
+            compile_exit_code: matches.get_one::<i32>("compile-exit-code").copied(),
+            exec_exit_code: matches.get_one::<i32>("exec-exit-code").copied(),
+            stdout_re: regex("stdout-match")?,
+            stderr_re: regex("stderr-match")?,
+            asm_re: regex("asm-match")?,
+            invert: *matches.get_one::<bool>("invert").unwrap(),
+        };
+
+        // Without any predicate, a failing compilation is what we are after.
+        if !predicate.exec_fail
+            && predicate.compile_exit_code.is_none()
+            && predicate.exec_exit_code.is_none()
+            && predicate.stdout_re.is_none()
+            && predicate.stderr_re.is_none()
+            && predicate.asm_re.is_none()
+        {
+            predicate.compile_fail = true;
+        }
+        Ok(predicate)
+    }
+
+    fn is_bad(&self, result: &CompileJobResult) -> bool {
+        let mut stdout = result.stdout.to_text();
+        let mut stderr = result.stderr.to_text();
+        if let Some(exec_result) = &result.execResult {
+            stdout = format!("{}\n{}", stdout, exec_result.stdout.to_text());
+            stderr = format!("{}\n{}", stderr, exec_result.stderr.to_text());
+        }
+
+        let matched = (self.compile_fail && result.code != 0)
+            || (self.exec_fail
+                && result
+                    .execResult
+                    .as_ref()
+                    .is_none_or(|exec_result| exec_result.code != 0))
+            || self.compile_exit_code == Some(result.code)
+            || self.exec_exit_code.is_some_and(|code| {
+                result
+                    .execResult
+                    .as_ref()
+                    .is_some_and(|exec_result| exec_result.code == code)
+            })
+            || self
+                .stdout_re
+                .as_ref()
+                .is_some_and(|re| re.is_match(&stdout))
+            || self
+                .stderr_re
+                .as_ref()
+                .is_some_and(|re| re.is_match(&stderr))
+            || self
+                .asm_re
+                .as_ref()
+                .is_some_and(|re| re.is_match(&result.asm.to_text()));
+
+        matched != self.invert
+    }
+}
+
+async fn bisect_step(
+    client: &Client,
+    compiler_info: &CompilerInfo,
+    job: &CompileJob,
+    predicate: &BisectPredicate,
+) -> Result<bool, Error> {
+    let result = client.compile(&compiler_info.id, job.clone()).await?;
+    let is_bad = predicate.is_bad(&result);
+
+    println!(
+        "{} \"{}\" ({}): {}",
+        (if is_bad { "✗".red() } else { "✔".green() }),
+        compiler_info.name,
+        compiler_info.id,
+        (if is_bad { "bad" } else { "good" }),
+    );
+    Ok(is_bad)
+}
+
+async fn do_bisect(client: &Client, matches: &ArgMatches) -> Result<(), Error> {
+    let predicate = BisectPredicate::from_matches(matches)?;
+    let mut filters_config = filters_from_matches(matches);
+    if predicate.exec_fail || predicate.exec_exit_code.is_some() {
+        filters_config.execute = true;
+    }
+
+    let source_data = source_from_matches(matches)?;
+    let flags = flags_from_matches(matches);
+    let (exec_args, exec_stdin) = execute_parameters_from_matches(matches)?;
+
+    let mut candidates = selected_compilers(client, matches, None).await?;
+    if filters_config.execute {
+        candidates.retain(|c| c.supportsExecute.unwrap_or(false));
+    }
+    candidates.sort_by(compare_semver);
+
+    if candidates.len() < 2 {
+        return Err(Error::BisectCandidates {
+            found: candidates.len(),
+        });
+    }
+
+    let job = CompileJob::build(&source_data, &flags, &filters_config)
+        .execute_parameters(exec_args, &exec_stdin);
+
+    let mut good = 0;
+    let mut bad = candidates.len() - 1;
+
+    if bisect_step(client, &candidates[good], &job, &predicate).await? {
+        return Err(Error::AlreadyBad {
+            compiler: candidates[good].name.clone(),
+        });
+    }
+    if !bisect_step(client, &candidates[bad], &job, &predicate).await? {
+        return Err(Error::StillGood {
+            compiler: candidates[bad].name.clone(),
+        });
+    }
+
+    while bad - good > 1 {
+        let mid = good + (bad - good) / 2;
+        if bisect_step(client, &candidates[mid], &job, &predicate).await? {
+            bad = mid;
+        } else {
+            good = mid;
+        }
+    }
+
+    println!(
+        "Last good: {} (\"{}\")",
+        candidates[good].id, candidates[good].name
+    );
+    println!(
+        "First bad: {} (\"{}\")",
+        candidates[bad].id, candidates[bad].name
+    );
+    Ok(())
+}
+
+/// Compiles the same source twice, with two compilers and/or two sets of
+/// flags, and prints the differences between the two assembly outputs.
+async fn do_diff(client: &Client, matches: &ArgMatches) -> Result<(), Error> {
+    let filters_config = filters_from_matches(matches);
+    let source_data = source_from_matches(matches)?;
+
+    let compilers = if let Some(ids) = matches.get_many::<String>("compiler-id") {
+        let mut compilers = Vec::new();
+        for id in ids {
+            compilers.push(get_compiler_info(client, id).await?);
+        }
+        compilers
+    } else {
+        let mut compilers = selected_compilers(client, matches, None).await?;
+        compilers.sort_by(compare_semver);
+        compilers
+    };
+    let flags = matches
+        .get_many::<String>("flags")
+        .map(|flags| flags.cloned().collect())
+        .unwrap_or_else(|| vec![String::new()]);
+
+    if compilers.len() > 2 || flags.len() > 2 || (compilers.len() == 1 && flags.len() == 1) {
+        return Err(Error::DiffSides {
+            compilers: compilers.len(),
+            flags: flags.len(),
+        });
+    }
+
+    // With a single compiler (or a single set of flags), both sides share it.
+    let sides = [0, 1].map(|i| {
+        (
+            &compilers[i.min(compilers.len() - 1)],
+            &flags[i.min(flags.len() - 1)],
+        )
+    });
+    let [left, right] = sides.map(|(compiler_info, flags)| {
+        let job = CompileJob::build(&source_data, flags, &filters_config);
+        client.compile(&compiler_info.id, job)
+    });
+    let (left, right) = futures::future::try_join(left, right).await?;
+
+    let mut unexpected = 0;
+    for ((compiler_info, _), result) in sides.iter().zip([&left, &right]) {
+        if result.code != 0 {
+            unexpected += 1;
+            eprintln!(
+                "{} Compilation \"{}\" ({})\n{}",
+                "✗".red(),
+                compiler_info.name,
+                result.code,
+                result.stderr.to_text()
+            );
+        }
+    }
+    if unexpected > 0 {
+        return Err(Error::Outcome {
+            unexpected,
+            total: 2,
+        });
+    }
+
+    let [left_name, right_name] =
+        sides.map(|(compiler_info, flags)| format!("{} {}", compiler_info.name, flags));
+    let [mut left_asm, mut right_asm] = [&left, &right].map(|result| {
+        result
+            .asm
+            .items()
+            .iter()
+            .map(|item| item.text.clone())
+            .collect::<Vec<String>>()
+    });
+    if *matches.get_one::<bool>("normalize").unwrap() {
+        left_asm = diff::normalize_asm(&left_asm);
+        right_asm = diff::normalize_asm(&right_asm);
+    }
+
+    if left_asm == right_asm {
+        println!(
+            "No difference between \"{}\" and \"{}\"",
+            left_name.trim_end(),
+            right_name.trim_end()
+        );
+    } else if *matches.get_one::<bool>("side-by-side").unwrap() {
+        println!(
+            "{}",
+            diff::side_by_side_diff(
+                &left_asm,
+                &right_asm,
+                left_name.trim_end(),
+                right_name.trim_end()
+            )
+        );
+    } else {
+        println!(
+            "{}",
+            diff::unified_diff(
+                &left_asm.join("\n"),
+                &right_asm.join("\n"),
+                left_name.trim_end(),
+                right_name.trim_end()
+            )
+        );
+    }
+    Ok(())
+}
+
+fn source_args() -> [Arg; 3] {
+    [
+        Arg::new("source")
+            .conflicts_with_all(["source-file", "file"])
+            .long("source"),
+        Arg::new("source-file")
+            .long("source-file")
+            .conflicts_with_all(["source", "file"])
+            .help("File holding the source (stdin if -)"),
+        Arg::new("file")
+            .conflicts_with_all(["source", "source-file"])
+            .help("Same as --source-file"),
+    ]
+}
+
+fn filter_args() -> [Arg; 4] {
+    [
+        Arg::new("binary")
+            .action(clap::ArgAction::SetTrue)
+            .long("binary")
+            .conflicts_with("binary-object")
+            .conflicts_with("execute"),
+        Arg::new("binary-object")
+            .action(clap::ArgAction::SetTrue)
+            .long("binary-object")
+            .conflicts_with("execute")
+            .conflicts_with("binary"),
+        Arg::new("execute")
+            .action(clap::ArgAction::SetTrue)
+            .long("execute")
+            .conflicts_with("binary-object")
+            .conflicts_with("binary"),
+        Arg::new("filters")
+            .long("filters")
+            .short('f')
+            .value_delimiter(',')
+            .value_parser(Filters::NAMES),
+    ]
+}
+
+fn compiler_selection_args() -> [Arg; 5] {
+    [
+        Arg::new("compiler-name").long("name"),
+        Arg::new("compiler-lang").long("language"),
+        Arg::new("compiler-isa").long("instruction-set"),
+        Arg::new("version-min").long("version-min"),
+        Arg::new("version-max").long("version-max"),
+    ]
+}
+
+/// Selection of compilers by capabilities, exclusions and version ranking,
+/// see `CompilerSelector`.
+fn project_args() -> [Arg; 2] {
+    [
+        Arg::new("project")
+            .long("project")
+            .value_name("dir")
+            .help("Compile all the files of dir, with CMake when it has a CMakeLists.txt"),
+        Arg::new("main")
+            .long("main")
+            .value_name("file")
+            .requires("project")
+            .help("Main file of --project, relative to dir, compiled without CMake"),
+    ]
+}
+
+fn selector_args() -> [Arg; 10] {
+    [
+        Arg::new("supports")
+            .long("supports")
+            .action(clap::ArgAction::Append)
+            .value_delimiter(',')
+            .value_parser(CompilerInfo::FEATURES)
+            .help("Only compilers supporting these features, can be repeated"),
+        Arg::new("category")
+            .long("category")
+            .action(clap::ArgAction::Append)
+            .help("Only compilers in this category, can be repeated"),
+        Arg::new("group")
+            .long("group")
+            .help("Only compilers of this group"),
+        Arg::new("nightly")
+            .long("nightly")
+            .action(clap::ArgAction::SetTrue)
+            .conflicts_with("no-nightly")
+            .help("Only nightly compilers"),
+        Arg::new("no-nightly")
+            .long("no-nightly")
+            .action(clap::ArgAction::SetTrue)
+            .help("Exclude nightly compilers"),
+        Arg::new("include-hidden")
+            .long("include-hidden")
+            .action(clap::ArgAction::SetTrue)
+            .help("Include hidden compilers"),
+        Arg::new("exclude-name")
+            .long("exclude-name")
+            .value_name("regex")
+            .help("Exclude compilers whose name matches regex"),
+        Arg::new("exclude-id")
+            .long("exclude-id")
+            .action(clap::ArgAction::Append)
+            .value_delimiter(',')
+            .help("Exclude compilers by id, can be repeated"),
+        Arg::new("latest")
+            .long("latest")
+            .value_name("N")
+            .value_parser(clap::value_parser!(u32).range(1..))
+            .help("Only the N newest compilers (by version)"),
+        Arg::new("latest-per-major")
+            .long("latest-per-major")
+            .action(clap::ArgAction::SetTrue)
+            .help("Only the newest compiler of each major version, per language, ISA and compiler type"),
+    ]
+}
+
+fn exec_args() -> [Arg; 2] {
+    [
+        Arg::new("exec-args")
+            .long("exec-arg")
+            .action(clap::ArgAction::Append)
+            .allow_hyphen_values(true)
+            .help("Argument given to the program when executing, can be repeated"),
+        Arg::new("exec-stdin")
+            .long("exec-stdin")
+            .help("File given as standard input when executing (stdin if -)"),
+    ]
+}
+
+/// Arguments controlling how compilation results are reported.
+fn output_args() -> [Arg; 11] {
+    [
+        Arg::new("summary")
+            .long("summary")
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("interleave")
+            .long("interleave")
+            .action(clap::ArgAction::SetTrue)
+            .conflicts_with("summary")
+            .help("Print each source line followed by its assembly"),
+        Arg::new("jobs")
+            .long("jobs")
+            .short('j')
+            .value_parser(clap::value_parser!(u8).range(1..=MAX_JOBS))
+            .default_value("1")
+            .help("Number of compilations running at the same time"),
+        Arg::new("fail-on")
+            .long("fail-on")
+            .value_parser(["any", "all", "none"])
+            .default_value("any")
+            .help("any: exit with 1 if one compiler fails, all: only if every one fails, none: never fail"),
+        Arg::new("expect-fail")
+            .long("expect-fail")
+            .action(clap::ArgAction::SetTrue)
+            .help("Compilation or execution is expected to fail"),
+        Arg::new("strict-filters")
+            .long("strict-filters")
+            .value_parser(["skip", "fail"])
+            .help("Skip compilers not supporting the requested filters, or fail"),
+        Arg::new("output-format")
+            .long("output-format")
+            .value_parser(["text", "json", "jsonl"])
+            .default_value("text")
+            .help("Print results as text, a JSON array or one JSON object per line"),
+        Arg::new("stdout")
+            .long("stdout")
+            .help("Write stdout to given file (stdout if -)"),
+        Arg::new("stderr")
+            .long("stderr")
+            .help("Write stderr to given file (stdout if -)"),
+        Arg::new("exec-stdout")
+            .long("exec-stdout")
+            .help("Write stdout of the execution to given file (stdout if -)"),
+        Arg::new("exec-stderr")
+            .long("exec-stderr")
+            .help("Write stderr of the execution to given file (stderr if -)"),
+    ]
 }
 
-#[derive(Debug, Clone)]
-enum OutputConfig {
-    Disable,
-    ToFile(String),
-    ToStdout,
+/// Reports `e` and exits with its code.
+fn exit_with_error(e: Error) -> ! {
+    eprintln!("{} {}", "Error:".red(), e);
+    std::process::exit(e.exit_code());
 }
 
 #[tokio::main]
@@ -413,12 +2009,63 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .version("0.1")
         .author("Marc Poulhiès <dkm@kataplop.net>")
         .about("Play with compiler-explorer")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
         .arg(
             Arg::new("base-url")
                 .long("base-url")
                 .default_value("https://godbolt.org"),
         )
+        .arg(
+            Arg::new("color")
+                .long("color")
+                .value_parser(["auto", "always", "never"])
+                .default_value("auto")
+                .help("Colorize the output, auto disables it when stdout isn't a terminal or NO_COLOR is set"),
+        )
+        .arg(
+            Arg::new("cache-ttl")
+                .long("cache-ttl")
+                .value_parser(clap::value_parser!(u64))
+                .default_value("86400")
+                .help("Seconds before cached compiler and language lists are revalidated"),
+        )
+        .arg(
+            Arg::new("refresh")
+                .long("refresh")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with("offline")
+                .help("Download compiler and language lists again"),
+        )
+        .arg(
+            Arg::new("offline")
+                .long("offline")
+                .action(clap::ArgAction::SetTrue)
+                .help("Only use cached compiler and language lists"),
+        )
+        .arg(
+            Arg::new("no-cache")
+                .long("no-cache")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with("offline")
+                .help("Don't read nor write the cache"),
+        )
         .subcommand(Command::new("list-languages"))
+        .subcommand(
+            Command::new("show-language")
+                .about("Show everything known about a language")
+                .arg(
+                    Arg::new("id")
+                        .required(true)
+                        .help("Language id (see list-languages)"),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .action(clap::ArgAction::SetTrue)
+                        .help("Print the language as JSON"),
+                ),
+        )
         .subcommand(
             Command::new("list-compilers")
                 .arg(Arg::new("all").action(clap::ArgAction::SetTrue).long("all"))
@@ -426,94 +2073,231 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .arg(Arg::new("isa").long("instruction-set"))
                 .arg(Arg::new("language").long("language"))
                 .arg(Arg::new("version-min").long("version-min"))
-                .arg(Arg::new("version-max").long("version-max")),
+                .arg(Arg::new("version-max").long("version-max"))
+                .args(selector_args()),
         )
         .subcommand(
-            Command::new("compile")
+            Command::new("show-compiler")
+                .about("Show everything known about a compiler")
                 .arg(
-                    Arg::new("source")
-                        .conflicts_with("source-file")
-                        .long("source"),
+                    Arg::new("id")
+                        .required(true)
+                        .help("Compiler id (see list-compilers)"),
                 )
                 .arg(
-                    Arg::new("binary")
+                    Arg::new("json")
+                        .long("json")
                         .action(clap::ArgAction::SetTrue)
-                        .long("binary")
-                        .conflicts_with("binary-object")
-                        .conflicts_with("execute"),
+                        .help("Print the compiler as JSON"),
+                ),
+        )
+        .subcommand(
+            Command::new("list-libraries").arg(
+                Arg::new("language")
+                    .long("language")
+                    .required(true)
+                    .help("Language id (see list-languages)"),
+            ),
+        )
+        .subcommand(
+            Command::new("list-tools").arg(
+                Arg::new("language")
+                    .long("language")
+                    .required(true)
+                    .help("Language id (see list-languages)"),
+            ),
+        )
+        .subcommand(
+            Command::new("compile")
+                .args(source_args())
+                .group(
+                    ArgGroup::new("source-group")
+                        .args(["source", "source-file", "file", "project"])
+                        .required(true)
+                        .multiple(false),
                 )
+                .args(project_args())
                 .arg(
-                    Arg::new("binary-object")
+                    Arg::new("bundle-includes")
+                        .long("bundle-includes")
                         .action(clap::ArgAction::SetTrue)
-                        .long("binary-object")
-                        .conflicts_with("execute")
-                        .conflicts_with("binary"),
+                        .conflicts_with("project")
+                        .help("Send the local headers included with #include \"...\" along the source"),
+                )
+                .arg(
+                    Arg::new("include-dirs")
+                        .short('I')
+                        .long("include-dir")
+                        .value_name("dir")
+                        .action(clap::ArgAction::Append)
+                        .requires("bundle-includes")
+                        .help("Look for the headers of --bundle-includes in dir, can be repeated"),
                 )
+                .args(filter_args())
+                .args(exec_args())
+                .args(expect_args())
+                .arg(Arg::new("compiler-id").long("id").conflicts_with_all([
+                    "compiler-name",
+                    "compiler-lang",
+                    "compiler-isa",
+                    "version-min",
+                    "version-max",
+                ]))
+                .args(compiler_selection_args())
+                .args(selector_args())
                 .arg(
-                    Arg::new("execute")
+                    Arg::new("all-compilers")
+                        .long("all-compilers")
                         .action(clap::ArgAction::SetTrue)
-                        .long("execute")
-                        .conflicts_with("binary-object")
-                        .conflicts_with("binary"),
+                        .help("When the language is the only compiler selection, use every compiler of the language instead of its default one"),
+                )
+                .arg(Arg::new("flags").allow_hyphen_values(true).long("flags"))
+                .arg(
+                    Arg::new("libs")
+                        .long("lib")
+                        .action(clap::ArgAction::Append)
+                        .value_name("id@version")
+                        .help("Use a library (see list-libraries), can be repeated"),
                 )
                 .arg(
-                    Arg::new("summary")
-                        .long("summary")
-                        .action(clap::ArgAction::SetTrue),
+                    Arg::new("tools")
+                        .long("tool")
+                        .action(clap::ArgAction::Append)
+                        .value_name("id[:args]")
+                        .help("Run a tool (see list-tools), can be repeated"),
                 )
                 .arg(
-                    Arg::new("source-file")
-                        .long("source-file")
-                        .conflicts_with("source"),
+                    Arg::new("share")
+                        .long("share")
+                        .action(clap::ArgAction::SetTrue)
+                        .conflicts_with("project")
+                        .help("Create a shortlink for this compilation"),
+                )
+                .args(output_args()),
+        )
+        .subcommand(
+            Command::new("run-link")
+                .about("Compile the sources of a Compiler-Explorer shortlink")
+                .arg(
+                    Arg::new("link")
+                        .required(true)
+                        .help("Shortlink id or URL (https://godbolt.org/z/<id>)"),
                 )
+                .args(compiler_selection_args())
+                .args(selector_args())
+                .args(expect_args())
+                .args(output_args()),
+        )
+        .subcommand(
+            Command::new("bisect")
+                .about("Find the first compiler version where the result goes bad")
+                .args(source_args())
                 .group(
                     ArgGroup::new("source-group")
-                        .args(["source", "source-file"])
+                        .args(["source", "source-file", "file"])
                         .required(true)
                         .multiple(false),
                 )
-                .arg(Arg::new("compiler-id").long("id"))
+                .args(filter_args())
+                .args(exec_args())
+                .args(compiler_selection_args())
+                .args(selector_args())
+                .arg(Arg::new("flags").allow_hyphen_values(true).long("flags"))
                 .arg(
-                    Arg::new("compiler-name")
-                        .long("name")
-                        .conflicts_with("compiler-id"),
+                    Arg::new("compile-fail")
+                        .long("compile-fail")
+                        .action(clap::ArgAction::SetTrue)
+                        .help("Bad if compilation fails (default without other predicate)"),
                 )
                 .arg(
-                    Arg::new("compiler-lang")
-                        .long("language")
-                        .conflicts_with("compiler-id"),
+                    Arg::new("exec-fail")
+                        .long("exec-fail")
+                        .action(clap::ArgAction::SetTrue)
+                        .conflicts_with_all(["binary", "binary-object"])
+                        .help("Bad if execution fails (implies --execute)"),
                 )
                 .arg(
-                    Arg::new("compiler-isa")
-                        .long("instruction-set")
-                        .conflicts_with("compiler-id"),
+                    Arg::new("compile-exit-code")
+                        .long("compile-exit-code")
+                        .allow_hyphen_values(true)
+                        .value_parser(clap::value_parser!(i32))
+                        .help("Bad if compilation exits with this code"),
                 )
                 .arg(
-                    Arg::new("version-min")
-                        .long("version-min")
-                        .conflicts_with("compiler-id"),
+                    Arg::new("exec-exit-code")
+                        .long("exec-exit-code")
+                        .allow_hyphen_values(true)
+                        .value_parser(clap::value_parser!(i32))
+                        .conflicts_with_all(["binary", "binary-object"])
+                        .help("Bad if execution exits with this code (implies --execute)"),
                 )
                 .arg(
-                    Arg::new("version-max")
-                        .long("version-max")
-                        .conflicts_with("compiler-id"),
+                    Arg::new("stdout-match")
+                        .long("stdout-match")
+                        .help("Bad if compiler or program stdout matches regex"),
                 )
-                .arg(Arg::new("flags").allow_hyphen_values(true).long("flags"))
                 .arg(
-                    Arg::new("stdout")
-                        .long("stdout")
-                        .help("Write stdout to given file (stdout if -)"),
+                    Arg::new("stderr-match")
+                        .long("stderr-match")
+                        .help("Bad if compiler or program stderr matches regex"),
+                )
+                .arg(
+                    Arg::new("asm-match")
+                        .long("asm-match")
+                        .help("Bad if assembly matches regex"),
+                )
+                .arg(
+                    Arg::new("invert")
+                        .long("invert")
+                        .action(clap::ArgAction::SetTrue)
+                        .help("Bad if none of the predicates hold"),
+                ),
+        )
+        .subcommand(
+            Command::new("diff")
+                .about("Show the assembly differences between two compilers or two sets of flags")
+                .args(source_args())
+                .group(
+                    ArgGroup::new("source-group")
+                        .args(["source", "source-file", "file"])
+                        .required(true)
+                        .multiple(false),
+                )
+                .args(filter_args())
+                .arg(
+                    Arg::new("compiler-id")
+                        .long("id")
+                        .action(clap::ArgAction::Append)
+                        .conflicts_with_all([
+                            "compiler-name",
+                            "compiler-lang",
+                            "compiler-isa",
+                            "version-min",
+                            "version-max",
+                        ])
+                        .help("Compiler id, can be given twice"),
+                )
+                .args(compiler_selection_args())
+                .args(selector_args())
+                .arg(
+                    Arg::new("flags")
+                        .long("flags")
+                        .allow_hyphen_values(true)
+                        .action(clap::ArgAction::Append)
+                        .help("Compiler flags, can be given twice"),
                 )
                 .arg(
-                    Arg::new("stderr")
-                        .long("stderr")
-                        .help("Write stderr to given file (stdout if -)"),
+                    Arg::new("side-by-side")
+                        .long("side-by-side")
+                        .short('y')
+                        .action(clap::ArgAction::SetTrue)
+                        .help("Print both outputs in two columns instead of a unified diff"),
                 )
                 .arg(
-                    Arg::new("filters")
-                        .long("filters")
-                        .short('f')
-                        .value_delimiter(','),
+                    Arg::new("normalize")
+                        .long("normalize")
+                        .action(clap::ArgAction::SetTrue)
+                        .help("Rename local labels and mask addresses before comparing"),
                 ),
         )
         .get_matches();
@@ -522,43 +2306,315 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .get_one::<String>("base-url")
         .expect("can't be missing");
 
-    let session = Session {
-        base_url: base_url.clone(),
-        client: reqwest::Client::builder().user_agent(concat!(
-            env!("CARGO_PKG_NAME"),
-            "/",
-            env!("CARGO_PKG_VERSION")
-        )).build()?,
-        // all_compilers: None,
-        // all_compilers_full: false,
+    match matches.get_one::<String>("color").unwrap().as_str() {
+        "always" => colored::control::set_override(true),
+        "never" => colored::control::set_override(false),
+        _ => (),
+    }
+
+    let client = Client::with_user_agent(
+        base_url,
+        concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")),
+    )
+    .unwrap_or_else(|e| exit_with_error(e.into()))
+    .cache(CacheConfig {
+        dir: if *matches.get_one::<bool>("no-cache").unwrap() {
+            None
+        } else {
+            CacheConfig::default_dir()
+        },
+        ttl: *matches.get_one::<u64>("cache-ttl").unwrap(),
+        refresh: *matches.get_one::<bool>("refresh").unwrap(),
+        offline: *matches.get_one::<bool>("offline").unwrap(),
+        on_write_error: Some(|path, e| {
+            eprintln!("Warning: can't write cache {}: {}", path.display(), e)
+        }),
+        on_stale_fallback: Some(|name, e| {
+            eprintln!(
+                "Warning: can't refresh {}, using the cached copy: {}",
+                name, e
+            )
+        }),
+    });
+
+    let result = match matches.subcommand() {
+        Some(("compile", sub_matches)) => do_compile(&client, sub_matches).await,
+        Some(("bisect", sub_matches)) => do_bisect(&client, sub_matches).await,
+        Some(("diff", sub_matches)) => do_diff(&client, sub_matches).await,
+        Some(("run-link", sub_matches)) => do_run_link(&client, sub_matches).await,
+        Some(("list-compilers", sub_matches)) => do_list_compilers(&client, sub_matches).await,
+        Some(("show-compiler", sub_matches)) => do_show_compiler(&client, sub_matches).await,
+        Some(("show-language", sub_matches)) => do_show_language(&client, sub_matches).await,
+        Some(("list-languages", submatches)) => do_list_languages(&client, submatches).await,
+        Some(("list-libraries", submatches)) => do_list_libraries(&client, submatches).await,
+        Some(("list-tools", submatches)) => do_list_tools(&client, submatches).await,
+        _ => unreachable!("subcommand_required prevents None"),
     };
 
-    match matches.subcommand() {
-        Some(("compile", sub_matches)) => do_compile(&session, sub_matches).await,
-        Some(("list-compilers", sub_matches)) => do_list_compilers(&session, sub_matches).await,
-        Some(("list-languages", submatches)) => do_list_languages(&session, submatches).await,
-        _ => println!("Woops"),
+    if let Err(e) = result {
+        exit_with_error(e);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn language(id: &str, extensions: &[&str], alias: &[&str]) -> Language {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": id,
+            "monaco": id,
+            "extensions": extensions,
+            "alias": alias,
+        }))
+        .unwrap()
+    }
+
+    fn languages() -> Vec<Language> {
+        vec![
+            language("c", &[".c", ".h"], &[]),
+            language("c++", &[".cpp", ".cxx", ".h", ".c", ".cc"], &["gcc", "cpp"]),
+            language("cppx", &[".cpp", ".h"], &[]),
+            language("circle", &[".cpp"], &[]),
+            language("rust", &[".rs"], &[]),
+        ]
+    }
+
+    fn language_id(extension: &str) -> Option<String> {
+        language_for_extension(&languages(), extension)
+            .unwrap()
+            .map(|l| l.id.clone())
+    }
+
+    #[test]
+    fn language_primary_extension() {
+        assert_eq!(language_id(".c").as_deref(), Some("c"));
+        assert_eq!(language_id(".rs").as_deref(), Some("rust"));
+        assert_eq!(language_id(".cc").as_deref(), Some("c++"));
     }
 
-    //     let lang = languages().await;
-    //     // println!("Languages: {:?}", lang);
+    #[test]
+    fn language_shared_primary_extension() {
+        assert_eq!(language_id(".cpp").as_deref(), Some("c++"));
 
-    //     let comps = compilers().await;
-    //     // println!("Compilers: {:?}", comps);
+        let without_cpp = languages()
+            .into_iter()
+            .filter(|l| l.id != "c++")
+            .collect::<Vec<_>>();
+        assert!(matches!(
+            language_for_extension(&without_cpp, ".cpp"),
+            Err(Error::AmbiguousLanguage { candidates, .. }) if candidates == "cppx, circle"
+        ));
+    }
 
-    //     let linkinfo = shortlinkinfo(&"hMh7fcbs1").await;
-    //     ///println!("linkinfo: {:?}", linkinfo);
+    #[test]
+    fn language_secondary_extension() {
+        assert!(matches!(
+            language_for_extension(&languages(), ".h"),
+            Err(Error::AmbiguousLanguage { candidates, .. }) if candidates == "c, c++, cppx"
+        ));
+    }
 
-    //     let linkinfo = shortlinkinfo(&"s6vGq7359").await;
-    //     // println!("linkinfo: {:?}", linkinfo); //
+    #[test]
+    fn language_unknown_extension() {
+        assert_eq!(language_id(".f90"), None);
+        assert_eq!(language_id(""), None);
+    }
 
-    //     let simple_job = CompileJob::build_simple("int main() {return 0;}", "");
-    //     let compile_ret1 = compile("cg412", simple_job).await;
-    // //    println!("compile job result: {:?}", compile_ret1);
-    //     let ret1 = compile_ret1.unwrap();
-    //     println!("stdout: {}", ret1.stdout.to_text());
-    //     println!("stderr: {}", ret1.stderr.to_text());
-    //     println!("asm: {}", ret1.asm.to_text());
+    /// Fresh directory for one test, with the given files.
+    fn tree(test: &str, files: &[(&str, &[u8])]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("ce-rs-{}-{}", test, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for (name, contents) in files {
+            let path = root.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        root
+    }
 
-    Ok(())
+    fn file_names(files: &[FiledataPair]) -> Vec<&str> {
+        files.iter().map(|f| f.filename.as_str()).collect()
+    }
+
+    fn project(args: &[&str]) -> Result<Option<(String, Vec<FiledataPair>, ProjectMode)>, Error> {
+        let matches = Command::new("test")
+            .args(project_args())
+            .get_matches_from(std::iter::once("test").chain(args.iter().copied()));
+        project_from_matches(&matches)
+    }
+
+    #[test]
+    fn project_dir_walk() {
+        let root = tree(
+            "walk",
+            &[
+                ("main.c", b"int main() {}\n"),
+                ("src/b.c", b"int b;\n"),
+                ("src/a.h", b"int a;\n"),
+                (".git/config", b"[core]\n"),
+                ("src/.hidden.c", b"int hidden;\n"),
+                ("logo.png", b"\x89PNG\r\n\x1a\n\xff\x00"),
+            ],
+        );
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(root.join("src"), root.join("loop")).unwrap();
+
+        let mut files = Vec::new();
+        read_project_dir(&root, &root, &mut files).unwrap();
+        assert_eq!(file_names(&files), ["main.c", "src/a.h", "src/b.c"]);
+        assert_eq!(files[2].contents, "int b;\n");
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn project_with_cmake() {
+        let root = tree(
+            "cmake",
+            &[
+                ("CMakeLists.txt", b"add_executable(main main.c)\n"),
+                ("main.c", b"int main() {}\n"),
+            ],
+        );
+        let (source, files, mode) = project(&["--project", root.to_str().unwrap()])
+            .unwrap()
+            .unwrap();
+        assert_eq!(source, "add_executable(main main.c)\n");
+        assert_eq!(file_names(&files), ["main.c"]);
+        assert!(matches!(mode, ProjectMode::CMake));
+
+        // --main bypasses CMake even when there is a CMakeLists.txt.
+        let (source, files, mode) =
+            project(&["--project", root.to_str().unwrap(), "--main", "./main.c"])
+                .unwrap()
+                .unwrap();
+        assert_eq!(source, "int main() {}\n");
+        assert_eq!(file_names(&files), ["CMakeLists.txt"]);
+        assert!(matches!(mode, ProjectMode::MainFile(name) if name == "main.c"));
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn project_without_main() {
+        let root = tree("nomain", &[("main.c", b"int main() {}\n")]);
+        let dir = root.to_str().unwrap();
+        assert!(matches!(
+            project(&["--project", dir]),
+            Err(Error::ProjectMain { .. })
+        ));
+        assert!(matches!(
+            project(&["--project", dir, "--main", "other.c"]),
+            Err(Error::Io { .. })
+        ));
+        assert!(project(&[]).unwrap().is_none());
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn project_languages() {
+        let files = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| FiledataPair {
+                    filename: name.to_string(),
+                    contents: String::new(),
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            project_language(&languages(), &files(&["main.c", "a.c", "b.h", "x.cpp"])),
+            "c"
+        );
+        assert_eq!(
+            project_language(&languages(), &files(&["src/main.cpp", "a.h"])),
+            "c++"
+        );
+        assert_eq!(
+            project_language(&languages(), &files(&["a.h", "README"])),
+            "c++"
+        );
+    }
+
+    fn compiler(
+        id: &str,
+        lang: &str,
+        isa: &str,
+        compiler_type: &str,
+        semver: &str,
+    ) -> CompilerInfo {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": id,
+            "lang": lang,
+            "compilerType": compiler_type,
+            "semver": semver,
+            "instructionSet": isa,
+        }))
+        .unwrap()
+    }
+
+    fn select(args: &[&str], compilers: Vec<CompilerInfo>) -> Vec<String> {
+        let matches = Command::new("test")
+            .args(selector_args())
+            .get_matches_from(std::iter::once("test").chain(args.iter().copied()));
+        CompilerSelector::from_matches(&matches)
+            .unwrap()
+            .select(compilers)
+            .into_iter()
+            .map(|c| c.id)
+            .collect()
+    }
+
+    fn catalog() -> Vec<CompilerInfo> {
+        vec![
+            compiler("g131", "c++", "amd64", "gcc", "13.1"),
+            compiler("g132", "c++", "amd64", "gcc", "13.2"),
+            compiler("g122", "c++", "amd64", "gcc", "12.2"),
+            compiler("cg132", "c", "amd64", "gcc", "13.2"),
+            compiler("armg131", "c++", "aarch64", "gcc", "13.1"),
+            compiler("clang131", "c++", "amd64", "clang", "13.0.1"),
+            compiler("gtrunk", "c++", "amd64", "gcc", "(trunk)"),
+            compiler("r1690", "rust", "amd64", "rust", "1.69.0"),
+            compiler("r1700", "rust", "amd64", "rust", "1.70.0"),
+        ]
+    }
+
+    #[test]
+    fn shortlink_ids() {
+        assert_eq!(shortlink_id("hMh7fcbs1"), "hMh7fcbs1");
+        assert_eq!(shortlink_id("https://godbolt.org/z/hMh7fcbs1"), "hMh7fcbs1");
+        assert_eq!(
+            shortlink_id("https://godbolt.org/z/hMh7fcbs1/"),
+            "hMh7fcbs1"
+        );
+    }
+
+    #[test]
+    fn select_without_ranking_keeps_everything() {
+        assert_eq!(select(&[], catalog()).len(), catalog().len());
+    }
+
+    #[test]
+    fn latest_per_major_by_family() {
+        let mut selected = select(&["--latest-per-major"], catalog());
+        selected.sort();
+        assert_eq!(
+            selected,
+            ["armg131", "cg132", "clang131", "g122", "g132", "r1700"]
+        );
+    }
+
+    #[test]
+    fn latest_sorted_by_version() {
+        assert_eq!(
+            select(
+                &["--latest", "2", "--exclude-id", "cg132,r1690,r1700"],
+                catalog()
+            ),
+            ["g131", "g132"]
+        );
+    }
 }
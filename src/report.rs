@@ -0,0 +1,213 @@
+/*
+ * This file is part of the ce-rs (https://github.com/dkm/ce-rs)
+ * Copyright (c) 2023 Marc Poulhiès <dkm@kataplop.net>.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use ce_api::types::{
+    AsmOutput, CompileJobResult, CompilerInfo, ExecutionResult, SomeOutput, ToolResult,
+};
+use serde::Serialize;
+
+/// Per-compiler record emitted by `--output-format json|jsonl`. Only the
+/// fields listed in the README are emitted, whatever the API returns.
+#[allow(non_snake_case)]
+#[derive(Serialize, Debug)]
+pub struct CompileReport<'a> {
+    pub compiler: CompilerReport<'a>,
+    pub code: i32,
+    pub timedOut: bool,
+    pub truncated: bool,
+    pub stdout: &'a SomeOutput,
+    pub stderr: &'a SomeOutput,
+    pub asm: &'a AsmOutput,
+    pub tools: Vec<ToolReport<'a>>,
+    pub execResult: Option<ExecReport<'a>>,
+    pub droppedFilters: &'a [&'static str],
+    pub failedExpectations: &'a [String],
+}
+
+#[derive(Serialize, Debug)]
+pub struct CompilerReport<'a> {
+    pub id: &'a str,
+    pub name: &'a str,
+    pub semver: &'a str,
+}
+
+#[allow(non_snake_case)]
+#[derive(Serialize, Debug)]
+pub struct ExecReport<'a> {
+    pub code: i32,
+    pub timedOut: bool,
+    pub stdout: &'a SomeOutput,
+    pub stderr: &'a SomeOutput,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ToolReport<'a> {
+    pub id: &'a str,
+    pub name: Option<&'a str>,
+    pub code: i32,
+    pub stdout: &'a SomeOutput,
+    pub stderr: &'a SomeOutput,
+}
+
+impl<'a> CompileReport<'a> {
+    pub fn new(
+        compiler: &'a CompilerInfo,
+        result: &'a CompileJobResult,
+        dropped_filters: &'a [&'static str],
+        failed_expectations: &'a [String],
+    ) -> Self {
+        CompileReport {
+            compiler: CompilerReport {
+                id: &compiler.id,
+                name: &compiler.name,
+                semver: &compiler.semver,
+            },
+            code: result.code,
+            timedOut: result.timedOut,
+            truncated: result.truncated,
+            stdout: &result.stdout,
+            stderr: &result.stderr,
+            asm: &result.asm,
+            tools: result.tools.iter().map(ToolReport::new).collect(),
+            execResult: result.execResult.as_ref().map(ExecReport::new),
+            droppedFilters: dropped_filters,
+            failedExpectations: failed_expectations,
+        }
+    }
+}
+
+impl<'a> ExecReport<'a> {
+    fn new(result: &'a ExecutionResult) -> Self {
+        ExecReport {
+            code: result.code,
+            timedOut: result.timedOut,
+            stdout: &result.stdout,
+            stderr: &result.stderr,
+        }
+    }
+}
+
+impl<'a> ToolReport<'a> {
+    fn new(result: &'a ToolResult) -> Self {
+        ToolReport {
+            id: &result.id,
+            name: result.name.as_deref(),
+            code: result.code,
+            stdout: &result.stdout,
+            stderr: &result.stderr,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(value: &serde_json::Value) -> Vec<&str> {
+        value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(|k| k.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn report_keys() {
+        let compiler: CompilerInfo = serde_json::from_value(serde_json::json!({
+            "id": "g132",
+            "name": "x86-64 gcc 13.2",
+            "lang": "c++",
+            "compilerType": "gcc",
+            "semver": "13.2",
+            "instructionSet": "amd64",
+        }))
+        .unwrap();
+        let output = serde_json::json!([{"text": "out", "tag": null}]);
+        let result: CompileJobResult = serde_json::from_value(serde_json::json!({
+            "inputFilename": "example.cpp",
+            "code": 0,
+            "okToCache": true,
+            "timedOut": false,
+            "stdout": output,
+            "stderr": [],
+            "truncated": false,
+            "execTime": "12",
+            "compilationOptions": [],
+            "downloads": [],
+            "tools": [{"id": "readelf", "name": "readelf", "code": 0, "stdout": [], "stderr": []}],
+            "asm": [{"text": "main:", "source": null, "labels": []}],
+            "labelDefinitions": {},
+            "parsingTime": "1",
+            "filteredCount": 0,
+            "execResult": {
+                "code": 1,
+                "okToCache": true,
+                "timedOut": false,
+                "stdout": output,
+                "stderr": [],
+                "didExecute": true,
+                "buildResult": {
+                    "code": 0,
+                    "okToCache": true,
+                    "timedOut": false,
+                    "truncated": false,
+                    "stdout": [],
+                    "stderr": [],
+                    "inputFilename": "example.cpp",
+                    "executableFilename": "output.s",
+                    "compilationOptions": [],
+                    "downloads": [],
+                    "execTime": "12",
+                },
+            },
+        }))
+        .unwrap();
+        let failed = vec!["exit code is 1, expected 0".to_string()];
+
+        let report =
+            serde_json::to_value(CompileReport::new(&compiler, &result, &["intel"], &failed))
+                .unwrap();
+        assert_eq!(
+            keys(&report),
+            [
+                "compiler",
+                "code",
+                "timedOut",
+                "truncated",
+                "stdout",
+                "stderr",
+                "asm",
+                "tools",
+                "execResult",
+                "droppedFilters",
+                "failedExpectations",
+            ]
+        );
+        assert_eq!(keys(&report["compiler"]), ["id", "name", "semver"]);
+        assert_eq!(
+            keys(&report["execResult"]),
+            ["code", "timedOut", "stdout", "stderr"]
+        );
+        assert_eq!(
+            keys(&report["tools"][0]),
+            ["id", "name", "code", "stdout", "stderr"]
+        );
+        assert_eq!(report["droppedFilters"], serde_json::json!(["intel"]));
+        assert_eq!(report["failedExpectations"], serde_json::json!(failed));
+    }
+}